@@ -1,6 +1,6 @@
 #!/bin/sh
-mv dist/*.wasm dist/journey.wasm
-mv dist/*.js dist/journey.js
+mv dist/rustjourneyplanner-*.wasm dist/journey.wasm
+mv dist/rustjourneyplanner-*.js dist/journey.js
 mv dist/*.css dist/journey.css
 mv dist/index.html dist/journey.htm
 sed -i "s/\/rustjourneyplanner\-.*\.js/\.\/journey\.js/g" dist/journey.htm
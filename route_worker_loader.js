@@ -0,0 +1,3 @@
+// Starts the routing worker, built by trunk from src/bin/route_worker.rs
+importScripts('./route_worker.js');
+wasm_bindgen('./route_worker_bg.wasm');
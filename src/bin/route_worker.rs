@@ -0,0 +1,43 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+use eve_route::{RouteService, WorkerRequest};
+
+// Builds the graph and searches for routes off the page's thread. Messages
+// waiting when one arrives are handled with it, so only the latest query of
+// each kind runs.
+fn main() {
+    console_error_panic_hook::set_once();
+
+    let scope = js_sys::global().unchecked_into::<DedicatedWorkerGlobalScope>();
+    let service = Rc::new(RefCell::new(RouteService::default()));
+    let pending = Rc::new(RefCell::new(Vec::<WorkerRequest>::new()));
+
+    let handle_pending : js_sys::Function = {
+        let (scope, pending) = (scope.clone(), pending.clone());
+        Closure::<dyn FnMut()>::new(move || {
+            let requests = pending.take();
+            for response in service.borrow_mut().handle(requests) {
+                let Ok(json) = serde_json::to_string(&response) else { continue };
+                let _ = scope.post_message(&JsValue::from_str(&json));
+            }
+        }).into_js_value().unchecked_into()
+    };
+
+    let on_message = Closure::<dyn FnMut(MessageEvent)>::new({
+        let scope = scope.clone();
+        move |event : MessageEvent| {
+            let Some(request) = event.data().as_string().and_then(|json| serde_json::from_str::<WorkerRequest>(&json).ok()) else { return };
+            let mut pending = pending.borrow_mut();
+            // A timeout runs after the messages already queued
+            if pending.is_empty() {
+                let _ = scope.set_timeout_with_callback_and_timeout_and_arguments_0(&handle_pending, 0);
+            }
+            pending.push(request);
+        }
+    });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    on_message.forget();
+}
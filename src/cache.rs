@@ -0,0 +1,98 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys;
+use tracing::warn;
+use eve_sde::*;
+
+use crate::provider::Connections;
+
+const SDE_STORAGE_KEY : &str = "journey-planner-sde"; // Where older releases kept it
+const SDE_CACHE_NAME : &str = "journey-planner";
+const SDE_CACHE_KEY : &str = "journey-planner-sde.bin";
+const WORMHOLE_TYPES_STORAGE_KEY : &str = "journey-planner-wormhole-types";
+const SNAPSHOT_STORAGE_KEY : &str = "journey-planner-snapshot";
+
+pub fn local_storage() -> Result<web_sys::Storage, String> {
+    web_sys::window().ok_or_else(|| format!("Cannot get browser window"))?
+        .local_storage().ok().flatten().ok_or_else(|| format!("Browser storage not available"))
+}
+
+fn get_item(key : &str) -> Option<String> {
+    local_storage().ok()?.get_item(key).ok()?
+}
+
+// Storage only fills up the next page load, so failing is only worth a warning.
+// The older copy is removed though, so it isn't taken for the current one.
+fn set_item(key : &str, value : &str) {
+    let Ok(storage) = local_storage() else { return };
+    if storage.set_item(key, value).is_err() {
+        warn!("Cannot store {}, browser storage is full", key);
+        let _ = storage.remove_item(key);
+    }
+}
+
+async fn sde_cache() -> Result<web_sys::Cache, String> {
+    let caches = web_sys::window().ok_or_else(|| format!("Cannot get browser window"))?
+        .caches().map_err(|_| format!("Browser cache not available"))?;
+    JsFuture::from(caches.open(SDE_CACHE_NAME)).await
+        .map_err(|_| format!("Cannot open browser cache"))?
+        .dyn_into::<web_sys::Cache>().map_err(|_| format!("Cannot open browser cache"))
+}
+
+// sde.bin is kept as it is in the Cache API, as local storage only holds strings
+// and is too small for it
+pub async fn get_cached_sde() -> Option<(SdeHeader, Sde)> {
+    // Left by older releases, and taking most of local storage
+    if let Ok(storage) = local_storage() { let _ = storage.remove_item(SDE_STORAGE_KEY); }
+
+    let response = JsFuture::from(sde_cache().await.ok()?.match_with_str(SDE_CACHE_KEY)).await.ok()?
+        .dyn_into::<web_sys::Response>().ok()?;
+    let buffer = JsFuture::from(response.array_buffer().ok()?).await.ok()?;
+    match decode_sde(&js_sys::Uint8Array::new(&buffer).to_vec()) {
+        Ok(sde) => Some(sde),
+        Err(e) => {
+            warn!("Ignoring stored SDE: {}", e);
+            None
+        }
+    }
+}
+
+pub async fn set_cached_sde(bytes : &[u8]) -> Result<(), String> {
+    let response = web_sys::Response::new_with_opt_u8_array(Some(&mut bytes.to_vec()))
+        .map_err(|_| format!("Cannot make a response for the SDE"))?;
+    JsFuture::from(sde_cache().await?.put_with_str(SDE_CACHE_KEY, &response)).await
+        .map_err(|_| format!("Browser cache is full"))?;
+    Ok(())
+}
+
+pub fn get_cached_wormhole_types() -> Option<String> {
+    get_item(WORMHOLE_TYPES_STORAGE_KEY)
+}
+
+pub fn set_cached_wormhole_types(json : &str) {
+    set_item(WORMHOLE_TYPES_STORAGE_KEY, json);
+}
+
+// The last connections a source gave, so the next visit starts with them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub time : NaiveDateTime, // When they were fetched
+    pub connections : Connections
+}
+
+fn snapshot_key(source : &str) -> String {
+    format!("{}-{}", SNAPSHOT_STORAGE_KEY, source)
+}
+
+pub fn get_snapshot(source : &str) -> Option<Snapshot> {
+    serde_json::from_str::<Snapshot>(&get_item(&snapshot_key(source))?).ok()
+}
+
+pub fn set_snapshot(source : &str, snapshot : &Snapshot) {
+    match serde_json::to_string(snapshot) {
+        Ok(json) => set_item(&snapshot_key(source), &json),
+        Err(e) => warn!("Cannot store {} connections: {:?}", source, e)
+    }
+}
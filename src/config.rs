@@ -0,0 +1,92 @@
+use serde::Deserialize;
+use web_sys;
+use tracing::info;
+
+use crate::provider::ProviderConfig;
+
+// Read from config.json next to journey.htm. Every field is optional, and
+// without the file the planner expects to be in Tripwire's public folder.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub tripwire_url : Option<String>, // Base URL of Tripwire, where refresh.php is
+    pub eve_scout_url : Option<String>, // EvE-Scout signatures JSON, defaults to Tripwire's cached copy
+    pub sde_url : Option<String>, // sde.bin, or sde.json for debugging
+    pub sde_hash_url : Option<String>, // Hash of sde.bin, to skip downloading it when the stored copy is current
+    pub wormhole_types_url : Option<String>,
+    pub tripwire_credentials : bool, // Send the Tripwire session cookie when Tripwire is on another origin
+    pub providers : Option<Vec<ProviderConfig>> // Defaults to the Tripwire and EvE-Scout above
+}
+
+// Directory of the page, so files copied next to journey.htm are found wherever it's hosted
+fn page_directory() -> Result<String, String> {
+    let href = web_sys::window().ok_or_else(|| format!("Cannot get page URL"))?
+        .location().href().map_err(|_| format!("Cannot get page URL"))?;
+    let href = href.split(['?', '#']).next().unwrap_or_default();
+    Ok(href.rsplit_once('/').map(|(directory, _)| directory).unwrap_or(href).to_owned())
+}
+
+fn origin() -> Result<String, String> {
+    Ok(web_sys::window().ok_or_else(|| format!("Cannot get base URL"))?.origin())
+}
+
+fn trim(url : &str) -> String {
+    url.trim_end_matches('/').to_owned()
+}
+
+impl Config {
+    pub fn tripwire_url(&self) -> Result<String, String> {
+        match &self.tripwire_url {
+            Some(url) => Ok(trim(url)),
+            None => origin()
+        }
+    }
+
+    pub fn eve_scout_url(&self) -> Result<String, String> {
+        match &self.eve_scout_url {
+            Some(url) => Ok(url.clone()),
+            None => Ok(format!("{}/cached_third_party.php?key=eve-scout-signatures", self.tripwire_url()?))
+        }
+    }
+
+    pub fn sde_url(&self) -> Result<String, String> {
+        match &self.sde_url {
+            Some(url) => Ok(url.clone()),
+            None => Ok(format!("{}/sde.bin", page_directory()?))
+        }
+    }
+
+    // None when there's nothing to check, such as sde.json for debugging
+    pub fn sde_hash_url(&self) -> Result<Option<String>, String> {
+        match (&self.sde_hash_url, &self.sde_url) {
+            (Some(url), _) => Ok(Some(url.clone())),
+            (None, None) => Ok(Some(format!("{}/sde.hash", page_directory()?))),
+            (None, Some(url)) => Ok(url.strip_suffix(".bin").map(|url| format!("{}.hash", url)))
+        }
+    }
+
+    pub fn wormhole_types_url(&self) -> Result<String, String> {
+        match &self.wormhole_types_url {
+            Some(url) => Ok(url.clone()),
+            None => Ok(format!("{}/wormholes.json", page_directory()?))
+        }
+    }
+}
+
+// A missing config.json means the defaults, but one that doesn't parse is an error
+pub async fn get_config() -> Result<Config, String> {
+    info!("Downloading config");
+
+    let response = reqwest::get(format!("{}/config.json", page_directory()?)).await
+        .map_err(|_| format!("Failed to send request for config.json"))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND { return Ok(Config::default()) }
+
+    let result = response
+        .error_for_status().map_err(|_| format!("Bad status code getting config.json"))?
+        .text().await
+        .map_err(|_| format!("Failed to get bytes for config.json"))?;
+
+    serde_json::from_str::<Config>(&result)
+        .map_err(|e| format!("Failed to parse config.json JSON: {:?}", e))
+}
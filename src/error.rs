@@ -1,3 +1,5 @@
+use eve_route::RouteError;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ErrorCategory {
     Loading, Input, Routing, Critical
@@ -24,3 +26,12 @@ pub fn routingerror<S: Into<String>>(description : S) -> ErrorStatus {
 pub fn criticalerror<S: Into<String>>(description : S) -> ErrorStatus {
     ErrorStatus { category : ErrorCategory::Critical, description : description.into() }
 }
+
+impl From<RouteError> for ErrorStatus {
+    fn from(e : RouteError) -> Self {
+        match e {
+            RouteError::MissingEdge => criticalerror(e.to_string()),
+            _ => routingerror(e.to_string())
+        }
+    }
+}
@@ -1,56 +1,135 @@
-use serde::Deserialize;
-use chrono::NaiveDateTime;
-use web_sys;
+use serde::{Deserialize, Deserializer};
+use chrono::{NaiveDateTime, Utc};
+use eve_route::{WormholeLife, WormholeMass, WormholeSide, WormholeSource, EOL_REMAINING_HOURS};
+
+use crate::provider::*;
+use crate::tripwire::SystemOrClass;
+
+// The API has given ids as both strings and numbers
+fn deserialize_record_id<'de, D>(deserializer: D) -> Result<String, D::Error> where D: Deserializer<'de> {
+    let v: serde_json::Value = Deserialize::deserialize(deserializer)?;
+    Ok(match v { serde_json::Value::String(s) => s, v => v.to_string() })
+}
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct EveScoutWormhole {
+    #[serde(deserialize_with = "deserialize_record_id")]
+    pub id : String,
+
     pub out_system_id : u32,
     pub out_signature : String,
     pub in_system_id : u32,
     pub in_signature : String,
     pub remaining_hours : u32,
     pub wh_type : String,
+
+    // True when the typed end is in out_system and in_system has the K162
+    #[serde(default)]
+    pub wh_exits_outward : Option<bool>,
+
+    #[serde(default)]
+    pub created_at : Option<String>,
+
     pub updated_at : String
 }
 
 #[derive(Debug, Clone)]
-pub struct EveScoutRefresh {
-    pub wormholes : Vec::<EveScoutWormhole>,
-    pub signature_count : usize,
-    pub signature_time : NaiveDateTime
+pub struct EveScoutProvider {
+    pub name : String,
+    pub url : String, // Signatures JSON
+    pub refresh_millis : u64,
+    pub policy : SourcePolicy
 }
 
-impl PartialEq for EveScoutRefresh {
-    fn eq(&self, other: &EveScoutRefresh) -> bool {
-        self.signature_time.eq(&other.signature_time) && self.signature_count.eq(&other.signature_count)
+impl ConnectionProvider for EveScoutProvider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn refresh_millis(&self) -> u64 {
+        self.refresh_millis
+    }
+
+    fn fetch(&self, previous : Option<Connections>) -> ConnectionsFuture {
+        Box::pin(get_eve_scout(self.clone(), previous))
+    }
+
+    fn apply_policy(&self, connections : Connections, now : NaiveDateTime) -> Connections {
+        self.policy.apply(connections, now)
     }
 }
 
-pub async fn get_eve_scout(_ : Option<EveScoutRefresh>) -> Result<EveScoutRefresh, String> {
+pub async fn get_eve_scout(provider : EveScoutProvider, _ : Option<Connections>) -> Result<Connections, ProviderError> {
     let client = reqwest::Client::new();
+    let fetch_time = Utc::now().naive_utc();
+
+    // Defaults to Tripwire's cached copy. A CORS proxy such as
+    // https://corsproxy.io/?https%3A%2F%2Fapi.eve-scout.com%2Fv2%2Fpublic%2Fsignatures
+    // also works, but seems to be aggressively cached, and is very out-of-date!
 
-    let baseurl = web_sys::window().ok_or_else(|| format!("Cannot get base URL"))?.origin();
+    let response = client.get(&provider.url)
+        .send().await.map_err(|_| ProviderError::Request(format!("EvE-Scout HTTP request failed")))?;
 
-    // URL using CORS proxy: format!("https://corsproxy.io/?{}", urlencoding::encode("https://api.eve-scout.com/v2/public/signatures"))
-    // This seems to be aggressively cached, and is very out-of-date!
+    if !response.status().is_success() { return Err(status_error(response.status(), None)) }
 
-    let result = client.get(format!("{baseurl}/cached_third_party.php?key=eve-scout-signatures"))   
-        .send().await.map_err(|_| format!("EvE-Scout HTTP request failed"))?
-        .error_for_status().map_err(|_| format!("EvE-Scout HTTP request failed"))?
-        .bytes().await.map_err(|_| format!("EvE-Scout HTTP request failed"))?;
+    let result = response
+        .bytes().await.map_err(|_| ProviderError::Request(format!("EvE-Scout HTTP request failed")))?;
 
     let wormholes = serde_json::from_slice::<Vec<EveScoutWormhole>>(&result)
-        .map_err(|_| format!("EvE-Scout JSON parse failed"))?;
+        .map_err(|_| ProviderError::Schema(format!("EvE-Scout JSON parse failed")))?;
 
     let signature_count = wormholes.len();
 
-    let signature_time = wormholes.iter()
-        .map(|v| NaiveDateTime::parse_from_str(&v.updated_at, "%Y-%m-%dT%H:%M:%S.000Z"))
-        .collect::<Result<Vec<_>,_>>()
-        .map_err(|_| format!("EvE-Scout failed to parse wormhole updated_at"))?
-        .into_iter()
-        .max()
-        .unwrap_or(NaiveDateTime::UNIX_EPOCH);
+    let parse_time = |t : &str| NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S.000Z")
+        .map_err(|_| ProviderError::Schema(format!("EvE-Scout failed to parse wormhole times")));
+
+    let updated = wormholes.iter()
+        .map(|v| parse_time(&v.updated_at))
+        .collect::<Result<Vec<_>,_>>()?;
+
+    let signature_time = updated.iter().copied().max().unwrap_or(NaiveDateTime::UNIX_EPOCH);
+
+    let mut provided = Vec::<ProvidedWormhole>::new();
+    let mut rejected = Vec::<RejectedWormhole>::new();
+
+    for (wormhole, updated) in wormholes.into_iter().zip(updated) {
+        let first_seen = wormhole.created_at.as_deref().and_then(|t| parse_time(t).ok()).unwrap_or(updated);
+
+        // Otherwise it's estimated from the type's lifetime, like Tripwire's
+        let (life, age) = match provider.policy.use_remaining_life.unwrap_or(false) {
+            true => {
+                let eol = wormhole.remaining_hours < EOL_REMAINING_HOURS as u32;
+                (if eol { WormholeLife::EOL } else { WormholeLife::Stable }, WormholeAge::RemainingHours(fetch_time, wormhole.remaining_hours))
+            },
+            false => (WormholeLife::Stable, WormholeAge::Since(first_seen))
+        };
+
+        let provided_wormhole = ProvidedWormhole {
+            from_system : wormhole.in_system_id,
+            to_system : SystemOrClass::SpecificSystem(wormhole.out_system_id),
+            from_signature : Some(wormhole.in_signature),
+            to_signature : Some(wormhole.out_signature),
+            wormhole_type : Some(wormhole.wh_type),
+            from_side : match wormhole.wh_exits_outward {
+                Some(true) => WormholeSide::K162,
+                Some(false) => WormholeSide::Origin,
+                None => WormholeSide::Unknown
+            },
+            life,
+            mass : WormholeMass::Stable,
+            age,
+            source : WormholeSource::EveScout,
+            record_id : Some(wormhole.id),
+            first_seen,
+            updated,
+            confidence : provider.policy.confidence(updated, fetch_time)
+        };
+
+        match provider.policy.too_old(first_seen, fetch_time) {
+            true => rejected.push(provided_wormhole.reject(RejectReason::TooOld)),
+            false => provided.push(provided_wormhole)
+        }
+    }
 
-    Ok(EveScoutRefresh { wormholes, signature_count, signature_time })
+    Ok(Connections { wormholes : provided, rejected, signature_count, signature_time })
 }
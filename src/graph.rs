@@ -1,134 +1,128 @@
 use std::collections::HashMap;
-use std::ops::Deref;
-use petgraph::graph::{Graph, NodeIndex};
-use eve_sde::System;
+use chrono::{Utc, Duration, DurationRound};
+use eve_sde::{System, WormholeTypes};
+use eve_route::*;
 use tracing::{info,warn};
 
 use crate::tripwire::*;
-use crate::eve_scout::*;
-use crate::helpers::*;
+use crate::provider::*;
 use crate::attr::*;
 
+// The wormholes from every source, ready for the routing worker to lay over
+// the gate graph, and the ones that couldn't be placed
 #[derive(Debug, Clone, PartialEq)]
-pub enum WormholeSource {
-    Tripwire,
-    EveScout
+pub struct Overlay {
+    pub edges : Vec<OverlayEdge>,
+    pub rejected : Vec<RejectedWormhole>
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct WormholeAttributes {
-    pub signature : Option<String>,
-    pub other_signature : Option<String>,
-    pub wormhole_type : Option<String>,
-    pub life : WormholeLife,
-    pub mass : WormholeMass,
-    pub jump_mass : Option<u32>,
-    pub source : WormholeSource
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum Connection {
-    Wormhole(WormholeAttributes),
-    Gate
-}
-
-pub fn get_graph(sde : Vec<System>, tripwire_refresh : Option<TripwireRefresh>, eve_scout_refresh : Option<EveScoutRefresh>) -> NeverEq<Graph<System, Connection>> {
-    info!("Constructing graph");
-    
-    let mut graph = Graph::<System, Connection>::new();
-    let mut node_index = HashMap::<u32, NodeIndex>::new();
-
-    for system in &sde {
-        node_index.insert(system.id, graph.add_node(system.clone()));
-    }
-
-    for system in &sde {
-        let index1 = *node_index.get(&system.id).unwrap(); // Can't be missing, we just added it
-        for neighbour in &system.neighbours {
-            let index2 = *node_index.get(neighbour).unwrap(); // Guaranteed consistent by eve_sde crate
-            graph.add_edge(index1, index2, Connection::Gate);
-            graph.add_edge(index2, index1, Connection::Gate);
-        }
+pub fn get_overlay(systems : &HashMap<u32, System>, wormhole_types : &WormholeTypes, connections : Vec<Connections>) -> Overlay {
+    info!("Constructing wormhole overlay");
+
+    let wormhole_attr = get_wormhole_attributes(wormhole_types);
+    let jump_mass_inference = JumpMassInference::new(wormhole_types);
+
+    // Whole minutes, so a refresh with nothing new gives an equal overlay and the routes aren't recalculated
+    let now = Utc::now().naive_utc();
+    let now = now.duration_trunc(Duration::minutes(1)).unwrap_or(now);
+
+    let mut edges = Vec::<OverlayEdge>::new();
+    let mut rejected = Vec::<RejectedWormhole>::new();
+
+    for wormhole in connections.into_iter().flat_map(|c| c.wormholes) {
+        let attr = wormhole.wormhole_type.as_ref().and_then(|t| wormhole_attr.get(t));
+        let expiry = match wormhole.age {
+            WormholeAge::Since(first_seen) => {
+                let lifetime = attr.and_then(|a| a.max_stable_time).map(|m| Duration::minutes(m as i64));
+                estimate_expiry(first_seen, lifetime, &wormhole.life, now)
+            },
+            WormholeAge::RemainingHours(reported, remaining_hours) => remaining_hours_expiry(reported, remaining_hours)
+        };
+
+        let Some(from_class) = systems.get(&wormhole.from_system).map(|s| s.class.clone()) else {
+            warn!("{:?} has a system not in the SDE", wormhole.source);
+            rejected.push(wormhole.reject(RejectReason::NotInSde));
+            continue;
+        };
+
+        // Far side not scouted yet, so keep it as a lead into its class
+        let (to, to_class) = match wormhole.to_system {
+            SystemOrClass::SpecificSystem(v) => match systems.get(&v) {
+                Some(to_system) => (OverlayTarget::System(v), to_system.class.clone()),
+                None => {
+                    warn!("{:?} has a system not in the SDE", wormhole.source);
+                    rejected.push(wormhole.reject(RejectReason::NotInSde));
+                    continue;
+                }
+            },
+            to_system => match to_system.class() {
+                Some(class) => (OverlayTarget::Lead(class.clone()), class),
+                None => {
+                    rejected.push(wormhole.reject(RejectReason::ToUnknown));
+                    continue
+                }
+            }
+        };
+
+        let origin_class = match wormhole.from_side {
+            WormholeSide::Origin => Some(&from_class),
+            WormholeSide::K162 => Some(&to_class),
+            WormholeSide::Unknown => None
+        };
+        let kind = WormholeKind::classify(attr.map(|a| a.static_in.as_slice()), origin_class);
+        let jump_mass_bound = match attr {
+            Some(_) => None,
+            None => jump_mass_inference.bound(&from_class, &to_class, &wormhole.from_side)
+        };
+
+        let forward = WormholeAttributes {
+            signature : wormhole.from_signature.clone(),
+            other_signature : wormhole.to_signature.clone(),
+            wormhole_type : wormhole.wormhole_type.clone(),
+            side : wormhole.from_side.clone(),
+            kind : kind.clone(),
+            life : wormhole.life.clone(),
+            mass : wormhole.mass.clone(),
+            jump_mass : attr.and_then(|a| a.jump_mass),
+            jump_mass_bound,
+            total_mass : attr.and_then(|a| a.total_mass),
+            mass_regeneration : attr.and_then(|a| a.mass_regeneration),
+            expiry : Some(expiry.clone()),
+            source : wormhole.source.clone(),
+            confidence : wormhole.confidence
+        };
+
+        let to_system = match to {
+            OverlayTarget::System(to_system) => to_system,
+            lead => {
+                edges.push(OverlayEdge { from : wormhole.from_system, to : lead, wormhole : forward });
+                continue
+            }
+        };
+
+        edges.push(OverlayEdge { from : wormhole.from_system, to : OverlayTarget::System(to_system), wormhole : forward });
+
+        edges.push(OverlayEdge {
+            from : to_system,
+            to : OverlayTarget::System(wormhole.from_system),
+            wormhole : WormholeAttributes {
+                signature : wormhole.to_signature,
+                other_signature : wormhole.from_signature,
+                wormhole_type : wormhole.wormhole_type,
+                side : wormhole.from_side.reverse(),
+                kind,
+                life : wormhole.life,
+                mass : wormhole.mass,
+                jump_mass : attr.and_then(|a| a.jump_mass),
+                jump_mass_bound,
+                total_mass : attr.and_then(|a| a.total_mass),
+                mass_regeneration : attr.and_then(|a| a.mass_regeneration),
+                expiry : Some(expiry),
+                source : wormhole.source,
+                confidence : wormhole.confidence
+            }
+        });
     }
 
-    if let Some(tripwire_refresh) = tripwire_refresh {
-        for wormhole in tripwire_refresh.wormholes {
-            let jump_mass = wormhole.wormhole_type.as_ref().and_then(|t| WORMHOLE_ATTR.deref().get(t).copied());
-
-            let to_system = match wormhole.to_system {
-                SystemOrClass::SpecificSystem(v) => v,
-                _ => continue
-            };
-
-            let [from_index, to_index] = match [wormhole.from_system, to_system].try_map(|s| { node_index.get(&s) }) {
-                Some(s) => s,
-                None => { warn!("Tripwire has a system not in the SDE"); continue; }
-            };
-
-            graph.add_edge(
-                *from_index, *to_index,
-                Connection::Wormhole(WormholeAttributes {
-                    signature : wormhole.from_signature.clone(),
-                    other_signature : wormhole.to_signature.clone(),
-                    wormhole_type : wormhole.wormhole_type.clone(),
-                    life : wormhole.life.clone(),
-                    mass : wormhole.mass.clone(),
-                    jump_mass : jump_mass.clone(),
-                    source : WormholeSource::Tripwire
-                })
-            );
-
-            graph.add_edge(
-                *to_index, *from_index,
-                Connection::Wormhole(WormholeAttributes {
-                    signature : wormhole.to_signature,
-                    other_signature : wormhole.from_signature,
-                    wormhole_type : wormhole.wormhole_type,
-                    life : wormhole.life,
-                    mass : wormhole.mass,
-                    jump_mass,
-                    source : WormholeSource::Tripwire
-                })
-            );
-        }
-    }
-
-    if let Some(eve_scout_refresh) = eve_scout_refresh {
-        for wormhole in eve_scout_refresh.wormholes {
-            let jump_mass = WORMHOLE_ATTR.get(&wormhole.wh_type).copied();
-
-            let [in_index, out_index] = match [wormhole.in_system_id, wormhole.out_system_id].try_map(|s| { node_index.get(&s) }) {
-                Some(s) => s,
-                None => { warn!("Eve-Scout has a system not in the SDE"); continue; }
-            };
-
-            graph.add_edge(
-                *in_index, *out_index,
-                Connection::Wormhole(WormholeAttributes {
-                    signature : Some(wormhole.in_signature.clone()),
-                    other_signature : Some(wormhole.out_signature.clone()),
-                    wormhole_type : Some(wormhole.wh_type.clone()),
-                    life : WormholeLife::Stable,
-                    mass : WormholeMass::Stable,
-                    jump_mass : jump_mass.clone(),
-                    source : WormholeSource::EveScout
-                })
-            );
-
-            graph.add_edge(
-                *out_index, *in_index,
-                Connection::Wormhole(WormholeAttributes {
-                    signature : Some(wormhole.out_signature.clone()),
-                    other_signature : Some(wormhole.in_signature.clone()),
-                    wormhole_type : Some(wormhole.wh_type.clone()),
-                    life : WormholeLife::Stable,
-                    mass : WormholeMass::Stable,
-                    jump_mass,
-                    source : WormholeSource::EveScout
-                })
-            );
-        }
-    }
-
-    NeverEq::<Graph::<System, Connection>> { value : graph }
-}
\ No newline at end of file
+    Overlay { edges, rejected }
+}
@@ -6,13 +6,12 @@
 use leptonic::prelude::*;
 use leptos::*;
 use leptos_icons::{BsIcon,CgIcon};
-use web_sys;
-use petgraph::algo;
-use petgraph::visit::IntoNodeReferences;
-use chrono::{Utc, Duration};
-use itertools::Itertools;
-use tracing::info;
+use std::rc::Rc;
+use std::collections::HashMap;
+use chrono::{Utc, Duration, NaiveDateTime};
+use tracing::{info, warn};
 use eve_sde::*;
+use eve_route::*;
 
 mod tripwire;
 mod graph;
@@ -21,12 +20,58 @@ mod helpers;
 mod attr;
 mod signals;
 mod eve_scout;
+mod config;
+mod provider;
+mod manual;
+mod cache;
+mod worker;
 
 use tripwire::*;
 use graph::*;
 use error::*;
+use helpers::*;
 use signals::*;
-use eve_scout::*;
+use config::*;
+use provider::*;
+use manual::*;
+use cache::*;
+use worker::*;
+
+const ROUTE_ALTERNATIVES : usize = 5;
+const MAX_LEADS : usize = 10;
+
+// A connection provider with its own refresh and on/off switch
+#[derive(Clone, Copy)]
+struct Source {
+    name : StoredValue<String>,
+    enabled : ReadSignal<bool>,
+    set_enabled : WriteSignal<bool>,
+    connections : Memo<Option<Connections>>,
+    tracker : Signal<Tracker<ProviderError>>
+}
+
+fn create_source(provider : Rc<dyn ConnectionProvider>) -> Source {
+    let (enabled, set_enabled) = create_signal(true);
+    let name = store_value(provider.name());
+    let millis = provider.refresh_millis();
+    let keeps_snapshot = provider.keeps_snapshot();
+    // Stored connections have aged since, so they go through the policy again too
+    let initial = keeps_snapshot.then(|| get_snapshot(&name.get_value())).flatten()
+        .map(|s| (provider.apply_policy(s.connections, Utc::now().naive_utc()), s.time));
+    let provider = store_value(provider);
+    let (connections, tracker) = create_tracked_local_resource(millis, enabled.into(), initial, move |previous| {
+        let fetch = provider.with_value(|p| p.fetch(previous));
+        async move {
+            let connections = fetch.await?;
+            let connections = provider.with_value(|p| p.apply_policy(connections, Utc::now().naive_utc()));
+            if keeps_snapshot {
+                set_snapshot(&name.get_value(), &Snapshot { time : Utc::now().naive_utc(), connections : connections.clone() });
+            }
+            Ok::<_, ProviderError>(connections)
+        }
+    });
+    Source { name, enabled, set_enabled, connections, tracker }
+}
 
 pub fn hhmmss(d : Duration) -> String {
     let ss = d.num_seconds();
@@ -37,41 +82,244 @@ pub fn hhmmss(d : Duration) -> String {
     format!("{}{:02}:{:02}:{:02}", if neg { "-" } else { "" }, h, m, s)
 }
 
-pub async fn get_sde() -> Result<Vec<System>, String> {
+pub fn hhmm(d : Duration) -> String {
+    let mm = d.num_minutes();
+    let neg = mm < 0;
+    let m = mm.abs() as u64;
+    let (h, m) = (m / 60, m % 60);
+    format!("{}{:02}:{:02}", if neg { "-" } else { "" }, h, m)
+}
+
+fn expires_in(expiry : &ExpiryWindow, now : NaiveDateTime) -> String {
+    format!("~{}", hhmm(expiry.likely() - now))
+}
+
+async fn get_sde_hash(url : String) -> Result<String, String> {
+    Ok(reqwest::get(url).await
+        .map_err(|_| format!("Failed to send request for the SDE hash"))?
+        .error_for_status().map_err(|_| format!("Bad status code getting the SDE hash"))?
+        .text().await
+        .map_err(|_| format!("Failed to get text for the SDE hash"))?
+        .trim().to_owned())
+}
+
+// None when the stored copy with the given header is current. sde.hash is
+// checked first, so a current copy doesn't need sde.bin downloaded again.
+pub async fn get_sde(config : Config, cached : Option<SdeHeader>) -> Result<Option<Vec<System>>, String> {
+    if let (Some(cached), Some(url)) = (&cached, config.sde_hash_url()?) {
+        match get_sde_hash(url).await {
+            Ok(hash) if hash == cached.content_hash_hex() => return Ok(None),
+            Ok(_) => info!("SDE has changed"),
+            Err(e) => warn!("Cannot check the SDE hash: {}", e)
+        }
+    }
+
     info!("Downloading SDE data");
 
-    let baseurl = web_sys::window().ok_or_else(|| format!("Cannot get base URL"))?.origin();
+    let result = reqwest::get(config.sde_url()?).await
+        .map_err(|_| format!("Failed to send request for the SDE"))?
+        .error_for_status().map_err(|_| format!("Bad status code getting the SDE"))?
+        .bytes().await
+        .map_err(|_| format!("Failed to get bytes for the SDE"))?;
+
+    // Normally sde.bin, but sde.json still works for debugging and isn't stored
+    let sde = match is_binary_sde(&result) {
+        true => {
+            let (header, sde) = decode_sde(&result).map_err(|e| format!("Cannot use sde.bin: {}", e))?;
+            info!("SDE build {}", header.sde_build);
+            if cached.is_some_and(|c| c.content_hash == header.content_hash) { return Ok(None) }
+            if let Err(e) = set_cached_sde(&result).await { warn!("Cannot store the SDE: {}", e) }
+            sde
+        },
+        false => Sde::from(serde_json::from_slice::<SdeFile>(&result)
+            .map_err(|e| format!("Failed to parse sde.json JSON: {:?}", e))?)
+    };
+
+    if sde.version > SDE_VERSION {
+        return Err(format!("sde.json is version {}, expected version {} or older", sde.version, SDE_VERSION));
+    }
+
+    Ok(Some(sde.systems))
+}
 
-    let result = reqwest::get(format!("{baseurl}/sde.json")).await
-        .map_err(|_| format!("Failed to send request for sde.json"))?
-        .error_for_status().map_err(|_| format!("Bad status code getting sde.json"))?
+pub fn parse_wormhole_types(json : &str) -> Result<WormholeTypes, String> {
+    let wormhole_types = serde_json::from_str::<WormholeTypes>(json)
+        .map_err(|e| format!("Failed to parse wormholes.json JSON: {:?}", e))?;
+
+    if wormhole_types.version != WORMHOLE_TYPES_VERSION {
+        return Err(format!("wormholes.json is version {}, expected version {}", wormhole_types.version, WORMHOLE_TYPES_VERSION));
+    }
+
+    Ok(wormhole_types)
+}
+
+// None when the download matches the stored copy
+pub async fn get_wormhole_types(config : Config, cached : Option<String>) -> Result<Option<WormholeTypes>, String> {
+    info!("Downloading wormhole type data");
+
+    let result = reqwest::get(config.wormhole_types_url()?).await
+        .map_err(|_| format!("Failed to send request for wormholes.json"))?
+        .error_for_status().map_err(|_| format!("Bad status code getting wormholes.json"))?
         .text().await
-        .map_err(|_| format!("Failed to get bytes for sde.json"))?;
+        .map_err(|_| format!("Failed to get bytes for wormholes.json"))?;
 
-    serde_json::from_str::<Vec<System>>(&result)
-        .map_err(|e| format!("Failed to parse sde.json JSON: {:?}", e))
+    if cached.as_ref() == Some(&result) { return Ok(None) }
+    let wormhole_types = parse_wormhole_types(&result)?;
+    set_cached_wormhole_types(&result);
+    Ok(Some(wormhole_types))
 }
 
+// Systems whose name matches come first, then systems in a matching region
 fn system_search_filter((s, o) : (String, Vec<System>)) -> Vec<System> {
     let lowercased_search = s.to_lowercase();
-    o.into_iter()
-        .filter(|it| {
+    let (by_name, rest) : (Vec<System>, Vec<System>) = o.into_iter()
+        .partition(|it| {
             it.name
                 .to_lowercase()
                 .starts_with(lowercased_search.as_str())
-        })
+        });
+    let by_region = rest.into_iter()
+        .filter(|it| {
+            !it.region_name.is_empty() &&
+            it.region_name
+                .to_lowercase()
+                .starts_with(lowercased_search.as_str())
+        });
+    by_name.into_iter()
+        .chain(by_region)
         .take(20)
         .collect::<Vec<System>>()
 }
 
+fn system_label(system : &System) -> String {
+    match system.region_name.is_empty() {
+        true => system.name.clone(),
+        false => format!("{} ({})", system.name, system.region_name)
+    }
+}
+
+// Such as "Enter via K162 (N110, static)", so scouts know which end they come through
+fn wormhole_type_label(wormhole : &WormholeAttributes) -> String {
+    let kind = match wormhole.kind {
+        WormholeKind::Static => Some("static"),
+        WormholeKind::Wandering => Some("wandering"),
+        WormholeKind::Unknown => None
+    };
+    let details = [wormhole.wormhole_type.as_deref().filter(|_| wormhole.side == WormholeSide::K162), kind]
+        .into_iter().flatten().collect::<Vec<_>>().join(", ");
+
+    match (wormhole.entry(), details.is_empty()) {
+        (Some(entry), true) => format!("Enter via {entry}"),
+        (Some(entry), false) => format!("Enter via {entry} ({details})"),
+        (None, _) => wormhole.wormhole_type.clone().unwrap_or("???".to_owned())
+    }
+}
+
+fn source_label(source : &WormholeSource) -> &'static str {
+    match source {
+        WormholeSource::Tripwire => "Tripwire",
+        WormholeSource::EveScout => "EvE-Scout",
+        WormholeSource::Manual => "Manual"
+    }
+}
+
+fn connection_summary(connection : &Connection) -> String {
+    match connection {
+        Connection::Gate => "Gate".to_owned(),
+        Connection::Jump(jump) => format!("Jump {:.2} LY", jump.light_years),
+        Connection::Wormhole(w) => {
+            let signature = w.signature.as_deref().unwrap_or("???")[..3].to_owned();
+            let mut flags = vec![wormhole_type_label(w), source_label(&w.source).to_owned()];
+            if w.life == WormholeLife::EOL { flags.push("EOL".to_owned()) }
+            match w.mass {
+                WormholeMass::Stable => (),
+                WormholeMass::Destab => flags.push("Destab".to_owned()),
+                WormholeMass::VOC => flags.push("VOC".to_owned())
+            };
+            format!("{} ({})", signature, flags.join(", "))
+        }
+    }
+}
+
+fn wormhole_summary(route : &RouteResult) -> String {
+    let wormholes = route.wormholes().map(|(system, w)| {
+        let mut flags = Vec::<&str>::new();
+        if w.life == WormholeLife::EOL { flags.push("EOL") }
+        match w.mass {
+            WormholeMass::Stable => (),
+            WormholeMass::Destab => flags.push("Destab"),
+            WormholeMass::VOC => flags.push("VOC")
+        };
+        let signature = w.signature.as_deref().unwrap_or("???")[..3].to_owned();
+        match flags.is_empty() {
+            true => format!("{} to {}", signature, system.name),
+            false => format!("{} to {} ({})", signature, system.name, flags.join(", "))
+        }
+    }).collect::<Vec<_>>();
+
+    match wormholes.is_empty() {
+        true => "Gates only".to_owned(),
+        false => wormholes.join(", ")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteProfile {
+    Shortest,
+    Safest,
+    Custom
+}
+
 #[component]
-pub fn App() -> impl IntoView {
-    let sde = create_local_resource(|| (), |_| async {
-        get_sde().await
+fn CostInput(label : &'static str, cost_model : ReadSignal<CostModel>, set_cost_model : WriteSignal<CostModel>, field : fn(&mut CostModel) -> &mut u32) -> impl IntoView {
+    view! {
+        <div style="width: 100%;">
+            <div style="margin-bottom: 5px;">{label}</div>
+            <NumberInput
+                min=0.0
+                step=1.0
+                get=Signal::derive(move || *field(&mut cost_model.get()) as f64)
+                set=move |v : f64| set_cost_model.update(|m| *field(m) = v.max(0.0).round() as u32)
+            />
+        </div>
+    }
+}
+
+#[component]
+pub fn App(config : Config, providers : Vec<Rc<dyn ConnectionProvider>>) -> impl IntoView {
+    let config = store_value(config);
+
+    // Start with the stored static data, if any, and download the latest in the background.
+    // A failed download only matters when nothing was stored.
+    let (sde, set_sde) = create_signal(None::<Result<Vec<System>, String>>);
+    spawn_local(async move {
+        let cached_sde = get_cached_sde().await;
+        let cached_header = cached_sde.as_ref().map(|(header, _)| header.clone());
+        let sde_cached = cached_header.is_some();
+        if let Some((_, cached_sde)) = cached_sde { set_sde.set(Some(Ok(cached_sde.systems))) }
+
+        match get_sde(config.get_value(), cached_header).await {
+            Ok(Some(systems)) => set_sde.set(Some(Ok(systems))),
+            Ok(None) => info!("Stored SDE is up to date"),
+            Err(e) if sde_cached => warn!("Using stored SDE: {}", e),
+            Err(e) => set_sde.set(Some(Err(e)))
+        }
     });
 
-    let (tripwire_memo, tripwire_tracker) = create_tracked_local_resource(5000, get_tripwire);
-    let (eve_scout_memo, eve_scout_tracker) = create_tracked_local_resource(30000, get_eve_scout);
+    let cached_wormhole_types = get_cached_wormhole_types().and_then(|json| parse_wormhole_types(&json).ok().map(|types| (json, types)));
+    let types_cached = cached_wormhole_types.is_some();
+    let (cached_json, cached_types) = cached_wormhole_types.unzip();
+    let (wormhole_types, set_wormhole_types) = create_signal(cached_types.map(Ok::<_, String>));
+    spawn_local(async move {
+        match get_wormhole_types(config.get_value(), cached_json).await {
+            Ok(Some(wormhole_types)) => set_wormhole_types.set(Some(Ok(wormhole_types))),
+            Ok(None) => info!("Stored wormhole types are up to date"),
+            Err(e) if types_cached => warn!("Using stored wormhole types: {}", e),
+            Err(e) => set_wormhole_types.set(Some(Err(e)))
+        }
+    });
+
+    let sources = store_value(providers.into_iter().map(create_source).collect::<Vec<_>>());
 
     let systems = Signal::derive(move ||  {
         match sde.get() {
@@ -82,80 +330,257 @@ pub fn App() -> impl IntoView {
         }
     });
 
-    let graph = create_memo(move |_|  {
-        Ok(get_graph(
-            sde.get().map_or_else(|| Err(loadingerror("Loading static data")), |v| v.map_err(|e| criticalerror(e)))?,
-            tripwire_memo.get(),
-            eve_scout_memo.get()
-        ))
+    // Systems by id, for placing wormholes and naming systems
+    let system_index = create_memo(move |_| -> Result<NeverEq<Rc<HashMap<u32, System>>>,ErrorStatus> {
+        let sde = sde.get().map_or_else(|| Err(loadingerror("Loading static data")), |v| v.map_err(|e| criticalerror(e)))?;
+        Ok(NeverEq { value : Rc::new(sde.into_iter().map(|s| (s.id, s)).collect()) })
+    });
+
+    // Warp across each system between its gates, where the SDE has their positions
+    let system_warp = create_memo(move |_| system_warps(&systems.get(), DEFAULT_WARP_SPEED));
+
+    // Compared by content, so a refresh with nothing new isn't sent to the worker
+    let overlay = create_memo(move |_| -> Result<Overlay,ErrorStatus> {
+        let systems = system_index.get()?.value;
+        let connections = sources.get_value().into_iter()
+            .filter(|source| source.enabled.get())
+            .filter_map(|source| source.connections.get())
+            .collect();
+        let start = Utc::now();
+        let wormhole_types = wormhole_types.get().map_or_else(|| Err(loadingerror("Loading wormhole types")), |v| v.map_err(|e| criticalerror(e)))?;
+        let overlay = get_overlay(&systems, &wormhole_types, connections);
+        info!("Built wormhole overlay in {} ms", (Utc::now() - start).num_milliseconds());
+        Ok(overlay)
     });
 
+    // Sources that are switched on but failing or still on stored data, so routes use
+    // their last data or none of it
+    let stale_sources = Signal::derive(move || {
+        sources.get_value().into_iter()
+            .filter(|source| source.enabled.get())
+            .filter_map(|source| {
+                let tracker = source.tracker.get();
+                (tracker.cached || tracker.update_error.is_some()).then(|| (source.name.get_value(), tracker.update_error, tracker.update_time, tracker.cached))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // Everything left out of the map, by the sources and when building the graph
+    let rejected = Signal::derive(move || {
+        let mut rejected = sources.get_value().into_iter()
+            .filter(|source| source.enabled.get())
+            .filter_map(|source| source.connections.get())
+            .flat_map(|c| c.rejected)
+            .collect::<Vec<_>>();
+        overlay.with(|o| if let Ok(o) = o { rejected.extend(o.rejected.iter().cloned()) });
+        rejected
+    });
+
+    let (show_diagnostics, set_show_diagnostics) = create_signal(false);
+
+    let system_name = move |id : u32| -> Option<String> {
+        system_index.with(|systems| systems.as_ref().ok()?.value.get(&id).map(|s| s.name.clone()))
+    };
+
     let (from_system, set_from_system) = create_signal(Option::<System>::None);
     let (to_system, set_to_system) = create_signal(Option::<System>::None);
+    let (via_systems, set_via_systems) = create_signal(Vec::<System>::new());
+    let (optimise_via_order, set_optimise_via_order) = create_signal(false);
     let (avoid_systems, set_avoid_systems) = create_signal(Vec::<System>::new());
     let (ship_size, set_ship_size) = create_signal((19u32, "Medium (up to Battlecruiser)".to_owned()));
+    let (unknown_jump_mass, set_unknown_jump_mass) = create_signal((UnknownJumpMass::Penalised, "Penalised".to_owned()));
     let (exclude_lowsec, set_exclude_lowsec) = create_signal(false);
     let (exclude_nullsec, set_exclude_nullsec) = create_signal(false);
     let (exclude_voc, set_exclude_voc) = create_signal(false);
     let (exclude_eol, set_exclude_eol) = create_signal(false);
     let (exclude_eve_scout, set_exclude_eve_scout) = create_signal(false);
+    let (min_confidence, set_min_confidence) = create_signal(0.0f64);
     let (exclude_zarzakh, set_exclude_zarzakh) = create_signal(false);
+    let (route_profile, set_route_profile) = create_signal((RouteProfile::Shortest, "Shortest".to_owned()));
+    let (custom_cost_model, set_custom_cost_model) = create_signal(CostModel::safest());
+    let (check_fleet_mass, set_check_fleet_mass) = create_signal(false);
+    let (fleet_ship_mass, set_fleet_ship_mass) = create_signal(100.0f64);
+    let (fleet_ship_count, set_fleet_ship_count) = create_signal(1.0f64);
+    let (fleet_round_trip, set_fleet_round_trip) = create_signal(true);
+    let (fleet_hours_before_return, set_fleet_hours_before_return) = create_signal(0.0f64);
+    let (exclude_expiring, set_exclude_expiring) = create_signal(false);
+    let (jump_drive_mode, set_jump_drive_mode) = create_signal(false);
+    let (jump_range, set_jump_range) = create_signal(7.0f64);
+    let (jump_fuel_per_light_year, set_jump_fuel_per_light_year) = create_signal(1500.0f64);
+    let (jump_fatigue_reduction, set_jump_fatigue_reduction) = create_signal(0.0f64);
+    let (jump_objective, set_jump_objective) = create_signal((JumpObjective::Jumps, "Fewest Jumps".to_owned()));
+    let default_travel_time = TravelTime::default();
+    let (gate_jump_seconds, set_gate_jump_seconds) = create_signal(default_travel_time.gate_jump.num_seconds() as f64);
+    let (wormhole_jump_seconds, set_wormhole_jump_seconds) = create_signal(default_travel_time.wormhole_jump.num_seconds() as f64);
+    let (warp_seconds, set_warp_seconds) = create_signal(default_travel_time.warp.num_seconds() as f64);
     
-    let route = Signal::derive(move || -> Result<Vec<(System,Connection)>,ErrorStatus> {
-        let graph = graph.get()?.value;
+    let (explore_class, set_explore_class) = create_signal((Option::<SystemClass>::None, "Off".to_owned()));
+    
+    let (selected_route, set_selected_route) = create_signal(0usize);
+
+    // What-if: wormholes on the route to pretend have collapsed, each picked out by its signatures
+    let (collapsed_wormholes, set_collapsed_wormholes) = create_signal(Vec::<CollapsedWormhole>::new());
+
+    let (manual_connections, set_manual_connections_signal) = create_signal(get_manual_connections().unwrap_or_default());
+    let (manual_from, set_manual_from) = create_signal(Option::<System>::None);
+    let (manual_to, set_manual_to) = create_signal(Option::<System>::None);
+    let (manual_signature, set_manual_signature) = create_signal(String::new());
+    let (manual_type, set_manual_type) = create_signal(String::new());
+    let (manual_life, set_manual_life) = create_signal((WormholeLife::Stable, "Stable".to_owned()));
+    let (manual_mass, set_manual_mass) = create_signal((WormholeMass::Stable, "Stable".to_owned()));
+    let (manual_hours, set_manual_hours) = create_signal(16.0f64);
+    let (manual_error, set_manual_error) = create_signal(Option::<String>::None);
+
+    // The manual provider reads storage on its next refresh
+    let save_manual_connections = move |connections : Vec<ManualConnection>| {
+        set_manual_error.set(set_manual_connections(&connections).err());
+        set_manual_connections_signal.set(connections);
+    };
+
+    let add_manual_connection = move || {
+        let (Some(from), Some(to)) = (manual_from.get(), manual_to.get()) else {
+            set_manual_error.set(Some("Select both systems".to_owned()));
+            return
+        };
+        // Signatures are cut to three letters when shown, so shorter ones are left out
+        let signature = Some(manual_signature.get().trim().to_uppercase()).filter(|s| s.len() >= 3);
+        let wormhole_type = Some(manual_type.get().trim().to_uppercase()).filter(|t| !t.is_empty());
+        let hours = manual_hours.get().clamp(1.0, 48.0) as u32;
+        let mut connections = manual_connections.get();
+        connections.push(ManualConnection::new(from.id, to.id, signature, wormhole_type, manual_life.get().0, manual_mass.get().0, hours));
+        save_manual_connections(connections);
+        set_manual_signature.set(String::new());
+        set_manual_type.set(String::new());
+    };
+
+    create_effect(move |_| {
+        let _ = (from_system.get(), to_system.get());
+        set_selected_route.set(0);
+        set_collapsed_wormholes.set(vec![]);
+    });
+
+    let request = create_memo(move |_| -> Result<RouteRequest,ErrorStatus> {
         let from_system = from_system.get().ok_or_else(|| inputerror("From system not selected"))?;
-        let to_system = to_system.get().ok_or_else(|| inputerror("To system not selected"))?;
-        let avoid_systems = avoid_systems.get();
+        let to_system = match explore_class.get().0 {
+            Some(_) => from_system.clone(), // Not used by exploratory routes
+            None => to_system.get().ok_or_else(|| inputerror("To system not selected"))?
+        };
         let (ship_size, _) = ship_size.get();
-        let exclude_lowsec = exclude_lowsec.get();
-        let exclude_nullsec = exclude_nullsec.get();
-        let exclude_voc = exclude_voc.get();
-        let exclude_eol = exclude_eol.get();
-        let exclude_eve_scout = exclude_eve_scout.get();
-        let exclude_zarzakh = exclude_zarzakh.get();
-
-        let filtered_graph = graph.filter_map(|_, system| {
-            if avoid_systems.contains(system) { return None }
-            if exclude_lowsec && system.class == SystemClass::Lowsec { return None }
-            if exclude_nullsec && system.class == SystemClass::Nullsec { return None }
-            if exclude_zarzakh && system.class == SystemClass::Zarzakh { return None }
-            Some(system.clone())
-        }, |_, connection| {
-            if let Connection::Wormhole(wormhole) = connection {
-                if exclude_voc && wormhole.mass == WormholeMass::VOC { return None }
-                if exclude_eol && wormhole.life == WormholeLife::EOL { return None }
-                if exclude_eve_scout && wormhole.source == WormholeSource::EveScout { return None }
-                if let Some(jump_mass) = wormhole.jump_mass {
-                    if ship_size > jump_mass { return None }
-                }
+
+        Ok(RouteRequest {
+            from_system : from_system.id,
+            to_system : to_system.id,
+            via_systems : via_systems.get().into_iter().map(|s| s.id).collect(),
+            optimise_via_order : optimise_via_order.get(),
+            avoid_systems : avoid_systems.get().into_iter().map(|s| s.id).collect(),
+            collapsed_wormholes : collapsed_wormholes.get(),
+            ship_size,
+            unknown_jump_mass : unknown_jump_mass.get().0,
+            exclude_lowsec : exclude_lowsec.get(),
+            exclude_nullsec : exclude_nullsec.get(),
+            exclude_zarzakh : exclude_zarzakh.get(),
+            exclude_voc : exclude_voc.get(),
+            exclude_eol : exclude_eol.get(),
+            exclude_eve_scout : exclude_eve_scout.get(),
+            min_confidence : min_confidence.get().clamp(0.0, 100.0) / 100.0,
+            cost_model : match route_profile.get() {
+                (RouteProfile::Shortest, _) => CostModel::shortest(),
+                (RouteProfile::Safest, _) => CostModel::safest(),
+                (RouteProfile::Custom, _) => custom_cost_model.get()
+            },
+            fleet : check_fleet_mass.get().then(|| Fleet {
+                ship_mass : fleet_ship_mass.get(),
+                ship_count : fleet_ship_count.get().max(1.0) as u32,
+                round_trip : fleet_round_trip.get(),
+                hours_before_return : fleet_hours_before_return.get()
+            }),
+            departure : None,
+            depart_now : true,
+            travel_time : TravelTime {
+                gate_jump : Duration::seconds(gate_jump_seconds.get().max(0.0) as i64),
+                wormhole_jump : Duration::seconds(wormhole_jump_seconds.get().max(0.0) as i64),
+                warp : Duration::seconds(warp_seconds.get().max(0.0) as i64),
+                system_warp : system_warp.get()
+            },
+            exclude_expiring : exclude_expiring.get(),
+            jump_drive : jump_drive_mode.get().then(|| JumpDrive {
+                range : jump_range.get().max(0.0),
+                fuel_per_light_year : jump_fuel_per_light_year.get().max(0.0),
+                fatigue_factor : 1.0 - jump_fatigue_reduction.get().clamp(0.0, 100.0) / 100.0,
+                objective : jump_objective.get().0
+            })
+        })
+    });
+
+    // Graph building and route searches run in the worker. Each kind of query
+    // keeps the id of the latest, so answers to older ones are ignored.
+    let (routes_answer, set_routes_answer) = create_signal(Err::<Vec<RouteResult>,ErrorStatus>(loadingerror("Calculating routes")));
+    let (leads, set_leads) = create_signal(Vec::<Lead>::new()); // Unscouted signatures that would most shorten the current route
+    let (uncollapsed_route, set_uncollapsed_route) = create_signal(Option::<RouteResult>::None); // The route before any simulated collapses, to compare against
+    let routes_query = store_value(Option::<u64>::None);
+    let leads_query = store_value(Option::<u64>::None);
+    let uncollapsed_query = store_value(Option::<u64>::None);
+
+    let on_response = move |response : WorkerResponse| match response {
+        WorkerResponse::Graph { systems, wormholes, millis } => info!("Built graph of {} systems and {} wormhole edges in {} ms", systems, wormholes, millis),
+        WorkerResponse::Cancelled(id) => info!("Query {} cancelled", id),
+        WorkerResponse::Answer { id, answer, millis } => {
+            info!("Answered query {} in {} ms", id, millis);
+            match answer {
+                Ok(RouteAnswer::Routes(routes)) if routes_query.get_value() == Some(id) => set_routes_answer.set(Ok(routes)),
+                Err(e) if routes_query.get_value() == Some(id) => set_routes_answer.set(Err(e.into())),
+                Ok(RouteAnswer::Leads(leads)) if leads_query.get_value() == Some(id) => set_leads.set(leads),
+                Ok(RouteAnswer::Route(route)) if uncollapsed_query.get_value() == Some(id) => set_uncollapsed_route.set(Some(route)),
+                _ => () // Superseded, or a lead or comparison that failed
             }
-            Some(connection.clone())
-        });
+        }
+    };
 
-        let (from_system_node, _) = filtered_graph.node_references().find(|(_, system)| {
-            system.id == from_system.id
-        }).ok_or_else(|| routingerror("From system not in graph. It was probably removed by the filtering rules."))?;
-
-        let (to_system_node, _) = filtered_graph.node_references().find(|(_, system)| {
-            system.id == to_system.id
-        }).ok_or_else(|| routingerror("To system not in graph. It was probably removed by the filtering rules."))?;
-
-        info!("Calculating shortest path");
-        let (_, path) = algo::astar(
-            &filtered_graph,
-            from_system_node,
-            |n| n == to_system_node,
-            |_| 1,
-            |_| 0,
-        ).ok_or_else(|| routingerror("No path between the systems"))?;
-
-        let path_details = path.into_iter().tuple_windows::<(_,_)>().map(|(n1, n2)| {
-            let connection = filtered_graph.edges_connecting(n1, n2).exactly_one().map_err(|_| criticalerror("Cannot find edge connecting nodes in graph"))?.weight().clone();
-            let node = filtered_graph[n2].clone();
-            Ok((node, connection))
-        }).collect::<Result<Vec<_>,ErrorStatus>>()?;
-
-        Ok(path_details)
+    let worker = store_value(RouteWorker::new(on_response));
+    let send = move |request : WorkerRequest| worker.with_value(|w| if let Ok(w) = w { w.send(&request) });
+    let ask = move |slot : StoredValue<Option<u64>>, query : Option<RouteQuery>| worker.with_value(|w| if let Ok(w) = w { w.ask(slot, query) });
+
+    // The worker rebuilds the graph and answers its latest queries again when these change
+    create_effect(move |_| if let Some(Ok(sde)) = sde.get() { send(WorkerRequest::Systems(sde)) });
+    create_effect(move |_| overlay.with(|overlay| if let Ok(overlay) = overlay { send(WorkerRequest::Overlay(overlay.edges.clone())) }));
+
+    create_effect(move |_| {
+        let explore = explore_class.get().0;
+        let query = request.get().ok().map(|request| RouteQuery::Routes { request, alternatives : ROUTE_ALTERNATIVES, explore });
+        set_routes_answer.set(Err(loadingerror("Calculating routes")));
+        ask(routes_query, query);
+    });
+
+    create_effect(move |_| {
+        let query = match (request.get(), explore_class.get()) {
+            (Ok(request), (None, _)) if request.jump_drive.is_none() => Some(RouteQuery::Leads { request, max : MAX_LEADS }),
+            _ => None
+        };
+        set_leads.set(vec![]);
+        ask(leads_query, query);
+    });
+
+    create_effect(move |_| {
+        let query = match (collapsed_wormholes.get().is_empty(), request.get()) {
+            (false, Ok(request)) => Some(RouteQuery::Route { request : RouteRequest { collapsed_wormholes : vec![], ..request } }),
+            _ => None
+        };
+        set_uncollapsed_route.set(None);
+        ask(uncollapsed_query, query);
+    });
+
+    // Problems with the data or the request come before anything the worker says
+    let routes = Signal::derive(move || -> Result<Vec<RouteResult>,ErrorStatus> {
+        worker.with_value(|w| w.as_ref().map(|_| ()).map_err(|e| criticalerror(e.clone())))?;
+        overlay.with(|overlay| overlay.as_ref().map(|_| ()).map_err(|e| e.clone()))?;
+        request.get()?;
+        routes_answer.get()
+    });
+
+    let route = Signal::derive(move || -> Result<RouteResult,ErrorStatus> {
+        let mut routes = routes.get()?;
+        let selected_route = selected_route.get().min(routes.len() - 1);
+        Ok(routes.swap_remove(selected_route))
     });
 
     let route_pastable = Signal::derive(move || -> String {
@@ -167,13 +592,23 @@ pub fn App() -> impl IntoView {
         let mut result = Vec::<String>::new();
         let mut previous_system : Option<System> = None;
         let mut previous_connection : Option<Connection> = None;
-        for (system, connection) in &route {
+        let mut previous_leg = 0usize;
+        for RouteHop { system, connection, leg, .. } in &route.hops {
+            // Mark the via system where each new leg begins
+            if *leg != previous_leg {
+                result.push(format!("[{}]", route.stops[*leg].name));
+                previous_leg = *leg;
+                previous_connection = None;
+            }
             if let Connection::Wormhole(w) = connection {
                 if let Some(s) = previous_system && previous_connection == Some(Connection::Gate) {
                     result.push(s.name.clone());
                 }
                 result.push(w.signature.as_deref().unwrap_or("???")[..3].to_owned());
             }
+            if let Connection::Jump(_) = connection {
+                result.push(system.name.clone());
+            }
             previous_system = Some(system.clone());
             previous_connection = Some(connection.clone());
         }
@@ -185,7 +620,7 @@ pub fn App() -> impl IntoView {
             };
         }
 
-        format!("> {}   ({} jumps)", result.join(" > "), route.len())
+        format!("> {}   ({} jumps)", result.join(" > "), route.jumps())
     });
 
     view! {
@@ -203,30 +638,31 @@ pub fn App() -> impl IntoView {
                         </div>
                     </Stack>
                     <Stack orientation=StackOrientation::Horizontal spacing=Size::Em(1.0)>
-                        <div>
-                            {move || {
-                                let tracker = eve_scout_tracker.get();
-                                match (tracker.update_time, tracker.update_error) {
-                                    (None, None) => view! { <div>"Loading EvE-Scout..."</div> }.into_view(),
-                                    (Some(update_time), Some(e)) => view! { <div class="redfg">{ format!("{}, EvE-Scout Update: {}", e, hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
-                                    (None, Some(e)) =>  view! { <div class="redfg">{ format!("{}", e) }</div> }.into_view(),
-                                    (Some(update_time), None) =>  view! { <div>{ format!("EvE-Scout Update: {}", hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
-                                }
-                            }}
-                        </div>
-                        " | "
-                        <div>
-                            {move || {
-                                let tracker = tripwire_tracker.get();
-                                match (tracker.update_time, tracker.update_error) {
-                                    (None, None) => view! { <div>"Loading Tripwire..."</div> }.into_view(),
-                                    (Some(update_time), Some(e)) => view! { <div class="redfg">{ format!("{}, Tripwire Update: {}", e, hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
-                                    (None, Some(e)) =>  view! { <div class="redfg">{ format!("{}", e) }</div> }.into_view(),
-                                    (Some(update_time), None) =>  view! { <div>{ format!("Tripwire Update: {}", hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
-                                }
-                            }}
-                        </div>
-                        
+                        {sources.get_value().into_iter().enumerate().map(|(i, source)| view! {
+                            {(i > 0).then(|| " | ")}
+                            <div>
+                                {move || {
+                                    let name = source.name.get_value();
+                                    let tracker = source.tracker.get();
+                                    let cached = tracker.cached;
+                                    match (source.enabled.get(), tracker.update_time, tracker.update_error) {
+                                        (false, _, _) => view! { <div>{ format!("{} Off", name) }</div> }.into_view(),
+                                        (true, None, None) => view! { <div>{ format!("Loading {}...", name) }</div> }.into_view(),
+                                        (true, update_time, Some(e)) => {
+                                            let age = update_time.map(|t| format!(", Update: {}", hhmmss(Utc::now().naive_utc() - t))).unwrap_or_default();
+                                            let login = e.login_url().map(|url| view! {
+                                                " "
+                                                <LinkExt href=url.to_owned() target=LinkExtTarget::Blank>"Log In"</LinkExt>
+                                            });
+                                            view! { <div class="redfg">{ format!("{}: {}{}", name, e, age) }{login}</div> }.into_view()
+                                        },
+                                        (true, Some(update_time), None) if cached => view! { <div class="orangefg">{ format!("{} Stored: {}", name, hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
+                                        (true, Some(update_time), None) =>  view! { <div>{ format!("{} Update: {}", name, hhmmss(Utc::now().naive_utc() - update_time)) }</div> }.into_view(),
+                                    }
+                                }}
+                            </div>
+                        }).collect_view()}
+
                         <LinkExt href="https://github.com/tordynnar/rustjourneyplanner" target=LinkExtTarget::Blank>
                             <Icon id="github-icon" icon=BsIcon::BsGithub aria_label="GitHub icon"/>
                         </LinkExt>
@@ -245,7 +681,7 @@ pub fn App() -> impl IntoView {
                                     options=systems
                                     search_text_provider=move |o : System| o.name
                                     search_filter_provider=system_search_filter
-                                    render_option=move |o : System| format!("{}", o.name)
+                                    render_option=move |o : System| system_label(&o)
                                     selected=move || from_system.get()
                                     set_selected=move |v| set_from_system.set(v)
                                     allow_deselect=true
@@ -274,7 +710,7 @@ pub fn App() -> impl IntoView {
                                     options=systems
                                     search_text_provider=move |o : System| o.name
                                     search_filter_provider=system_search_filter
-                                    render_option=move |o : System| format!("{}", o.name)
+                                    render_option=move |o : System| system_label(&o)
                                     selected=move || to_system.get()
                                     set_selected=move |v| set_to_system.set(v)
                                     allow_deselect=true
@@ -282,6 +718,52 @@ pub fn App() -> impl IntoView {
                             </div>
                         </Col>
                     </Row>
+                    <Row>
+                        <Col md=12>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Via Systems"</div>
+                                <Multiselect
+                                    options=systems
+                                    search_text_provider=move |o : System| o.name
+                                    search_filter_provider=system_search_filter
+                                    render_option=move |o : System| system_label(&o)
+                                    selected=move || via_systems.get()
+                                    set_selected=move |v| set_via_systems.set(v)
+                                />
+                            </div>
+                            <div class="toggle">
+                                <Toggle state=optimise_via_order set_state=set_optimise_via_order/>
+                                <label>{ format!("Optimise Via Order (up to {} systems)", MAX_OPTIMISED_VIA_SYSTEMS) }</label>
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=12>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Explore (nearest system of a class, including unscouted signatures)"</div>
+                                <Select
+                                    options=vec![
+                                        (None, "Off".to_owned()),
+                                        (Some(SystemClass::Highsec), "Any Highsec".to_owned()),
+                                        (Some(SystemClass::Lowsec), "Any Lowsec".to_owned()),
+                                        (Some(SystemClass::Nullsec), "Any Nullsec".to_owned()),
+                                        (Some(SystemClass::C1), "Any C1".to_owned()),
+                                        (Some(SystemClass::C2), "Any C2".to_owned()),
+                                        (Some(SystemClass::C3), "Any C3".to_owned()),
+                                        (Some(SystemClass::C4), "Any C4".to_owned()),
+                                        (Some(SystemClass::C5), "Any C5".to_owned()),
+                                        (Some(SystemClass::C6), "Any C6".to_owned()),
+                                        (Some(SystemClass::C13), "Any C13".to_owned()),
+                                        (Some(SystemClass::Pochven), "Any Pochven".to_owned()),
+                                    ]
+                                    search_text_provider=move |(_, desc) : (Option<SystemClass>, String)| desc
+                                    render_option=move |(_, desc) : (Option<SystemClass>, String)| desc
+                                    selected=move || explore_class.get()
+                                    set_selected=move |v| set_explore_class.set(v)
+                                />
+                            </div>
+                        </Col>
+                    </Row>
                     <Row>
                         <Col md=12>
                             <div style="width: 100%;">
@@ -290,7 +772,7 @@ pub fn App() -> impl IntoView {
                                     options=systems
                                     search_text_provider=move |o : System| o.name
                                     search_filter_provider=system_search_filter
-                                    render_option=move |o : System| format!("{}", o.name)
+                                    render_option=move |o : System| system_label(&o)
                                     selected=move || avoid_systems.get()
                                     set_selected=move |v| set_avoid_systems.set(v)
                                 />
@@ -298,7 +780,7 @@ pub fn App() -> impl IntoView {
                         </Col>
                     </Row>
                     <Row>
-                        <Col md=12>
+                        <Col md=6>
                             <div style="width: 100%;">
                                 <div style="margin-bottom: 5px;">"Ship Size"</div>
                                 <Select
@@ -315,7 +797,58 @@ pub fn App() -> impl IntoView {
                                 />
                             </div>
                         </Col>
+                        <Col md=6>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Holes of Unknown Size"</div>
+                                <Select
+                                    options=vec![
+                                        (UnknownJumpMass::Passable, "Passable".to_owned()),
+                                        (UnknownJumpMass::Penalised, "Penalised".to_owned()),
+                                        (UnknownJumpMass::Impassable, "Impassable".to_owned()),
+                                    ]
+                                    search_text_provider=move |(_, desc) : (UnknownJumpMass, String)| desc
+                                    render_option=move |(_, desc) : (UnknownJumpMass, String)| desc
+                                    selected=move || unknown_jump_mass.get()
+                                    set_selected=move |v| set_unknown_jump_mass.set(v)
+                                />
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=12>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Route Preference"</div>
+                                <Select
+                                    options=vec![
+                                        (RouteProfile::Shortest, "Shortest".to_owned()),
+                                        (RouteProfile::Safest, "Safest".to_owned()),
+                                        (RouteProfile::Custom, "Custom".to_owned()),
+                                    ]
+                                    search_text_provider=move |(_, desc) : (RouteProfile, String)| desc
+                                    render_option=move |(_, desc) : (RouteProfile, String)| desc
+                                    selected=move || route_profile.get()
+                                    set_selected=move |v| set_route_profile.set(v)
+                                />
+                            </div>
+                        </Col>
                     </Row>
+                    {move || (route_profile.get().0 == RouteProfile::Custom).then(|| view! {
+                        <Row>
+                            <Col md=3><CostInput label="Jump Cost" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.jump/></Col>
+                            <Col md=3><CostInput label="Lowsec Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.lowsec/></Col>
+                            <Col md=3><CostInput label="Nullsec Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.nullsec/></Col>
+                            <Col md=3><CostInput label="EvE-Scout Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.eve_scout/></Col>
+                        </Row>
+                        <Row>
+                            <Col md=3><CostInput label="EOL Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.eol/></Col>
+                            <Col md=3><CostInput label="VOC Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.voc/></Col>
+                            <Col md=3><CostInput label="Destab Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.destab/></Col>
+                            <Col md=3><CostInput label="Unknown Jump Mass Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.unknown_jump_mass/></Col>
+                        </Row>
+                        <Row>
+                            <Col md=3><CostInput label="Low Confidence Penalty" cost_model=custom_cost_model set_cost_model=set_custom_cost_model field=|m| &mut m.low_confidence/></Col>
+                        </Row>
+                    })}
                     <Row>
                         <Col md=4>
                             <div class="toggle">
@@ -356,6 +889,229 @@ pub fn App() -> impl IntoView {
                             </div>
                         </Col>
                     </Row>
+                    <Row>
+                        <Col md=4>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Minimum Confidence (%)"</div>
+                                <NumberInput min=0.0 step=10.0 get=min_confidence set=set_min_confidence/>
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        {sources.get_value().into_iter().map(|source| view! {
+                            <Col md=4>
+                                <div class="toggle">
+                                    <Toggle state=source.enabled set_state=source.set_enabled/>
+                                    <label>{ format!("Use {}", source.name.get_value()) }</label>
+                                </div>
+                            </Col>
+                        }).collect_view()}
+                        <Col md=4>
+                            <div class="toggle">
+                                <Toggle state=show_diagnostics set_state=set_show_diagnostics/>
+                                <label>{ move || format!("Show Diagnostics ({} rejected)", rejected.get().len()) }</label>
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=6>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Manual Connection From"</div>
+                                <OptionalSelect
+                                    options=systems
+                                    search_text_provider=move |o : System| o.name
+                                    search_filter_provider=system_search_filter
+                                    render_option=move |o : System| system_label(&o)
+                                    selected=move || manual_from.get()
+                                    set_selected=move |v| set_manual_from.set(v)
+                                    allow_deselect=true
+                                />
+                            </div>
+                        </Col>
+                        <Col md=6>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Manual Connection To"</div>
+                                <OptionalSelect
+                                    options=systems
+                                    search_text_provider=move |o : System| o.name
+                                    search_filter_provider=system_search_filter
+                                    render_option=move |o : System| system_label(&o)
+                                    selected=move || manual_to.get()
+                                    set_selected=move |v| set_manual_to.set(v)
+                                    allow_deselect=true
+                                />
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Signature"</div>
+                                <TextInput get=manual_signature set=move |v| set_manual_signature.set(v)/>
+                            </div>
+                        </Col>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Type"</div>
+                                <TextInput get=manual_type set=move |v| set_manual_type.set(v)/>
+                            </div>
+                        </Col>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Life"</div>
+                                <Select
+                                    options=vec![
+                                        (WormholeLife::Stable, "Stable".to_owned()),
+                                        (WormholeLife::EOL, "EOL".to_owned()),
+                                    ]
+                                    search_text_provider=move |(_, desc) : (WormholeLife, String)| desc
+                                    render_option=move |(_, desc) : (WormholeLife, String)| desc
+                                    selected=move || manual_life.get()
+                                    set_selected=move |v| set_manual_life.set(v)
+                                />
+                            </div>
+                        </Col>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Mass"</div>
+                                <Select
+                                    options=vec![
+                                        (WormholeMass::Stable, "Stable".to_owned()),
+                                        (WormholeMass::Destab, "Destab".to_owned()),
+                                        (WormholeMass::VOC, "VOC".to_owned()),
+                                    ]
+                                    search_text_provider=move |(_, desc) : (WormholeMass, String)| desc
+                                    render_option=move |(_, desc) : (WormholeMass, String)| desc
+                                    selected=move || manual_mass.get()
+                                    set_selected=move |v| set_manual_mass.set(v)
+                                />
+                            </div>
+                        </Col>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Expires After (h)"</div>
+                                <NumberInput min=1.0 step=1.0 get=manual_hours set=set_manual_hours/>
+                            </div>
+                        </Col>
+                        <Col md=2>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">" "</div>
+                                <leptonic-link>
+                                    <a on:click=move |_| add_manual_connection()>"Add Connection"</a>
+                                </leptonic-link>
+                            </div>
+                        </Col>
+                    </Row>
+                    {move || manual_error.get().map(|e| view! {
+                        <Row>
+                            <Col md=12>
+                                <div class="redfg">{e}</div>
+                            </Col>
+                        </Row>
+                    })}
+                    <Row>
+                        <Col md=4>
+                            <div class="toggle">
+                                <Toggle state=exclude_expiring set_state=set_exclude_expiring/>
+                                <label>"Exclude Likely Expired"</label>
+                            </div>
+                        </Col>
+                        <Col md=8>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Gate Jump (s)"</div>
+                                <NumberInput min=0.0 step=5.0 get=gate_jump_seconds set=set_gate_jump_seconds/>
+                            </div>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Wormhole Jump (s)"</div>
+                                <NumberInput min=0.0 step=5.0 get=wormhole_jump_seconds set=set_wormhole_jump_seconds/>
+                            </div>
+                            <div style="width: 100%;">
+                                <div style="margin-bottom: 5px;">"Default Warp (s)"</div>
+                                <NumberInput min=0.0 step=5.0 get=warp_seconds set=set_warp_seconds/>
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=4>
+                            <div class="toggle">
+                                <Toggle state=check_fleet_mass set_state=set_check_fleet_mass/>
+                                <label>"Check Fleet Mass"</label>
+                            </div>
+                        </Col>
+                        <Col md=4>
+                            <div class="toggle">
+                                <Toggle state=fleet_round_trip set_state=set_fleet_round_trip/>
+                                <label>"Round Trip"</label>
+                            </div>
+                        </Col>
+                    </Row>
+                    <Row>
+                        <Col md=4>
+                            <div class="toggle">
+                                <Toggle state=jump_drive_mode set_state=set_jump_drive_mode/>
+                                <label>"Jump Drive"</label>
+                            </div>
+                        </Col>
+                    </Row>
+                    {move || jump_drive_mode.get().then(|| view! {
+                        <Row>
+                            <Col md=3>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Jump Range (LY)"</div>
+                                    <NumberInput min=0.0 step=0.5 get=jump_range set=set_jump_range/>
+                                </div>
+                            </Col>
+                            <Col md=3>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Fuel per LY"</div>
+                                    <NumberInput min=0.0 step=100.0 get=jump_fuel_per_light_year set=set_jump_fuel_per_light_year/>
+                                </div>
+                            </Col>
+                            <Col md=3>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Fatigue Reduction (%)"</div>
+                                    <NumberInput min=0.0 max=100.0 step=10.0 get=jump_fatigue_reduction set=set_jump_fatigue_reduction/>
+                                </div>
+                            </Col>
+                            <Col md=3>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Minimise"</div>
+                                    <Select
+                                        options=vec![
+                                            (JumpObjective::Jumps, "Fewest Jumps".to_owned()),
+                                            (JumpObjective::Fatigue, "Least Fatigue".to_owned()),
+                                        ]
+                                        search_text_provider=move |(_, desc) : (JumpObjective, String)| desc
+                                        render_option=move |(_, desc) : (JumpObjective, String)| desc
+                                        selected=move || jump_objective.get()
+                                        set_selected=move |v| set_jump_objective.set(v)
+                                    />
+                                </div>
+                            </Col>
+                        </Row>
+                    })}
+                    {move || check_fleet_mass.get().then(|| view! {
+                        <Row>
+                            <Col md=4>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Ship Mass (million kg)"</div>
+                                    <NumberInput min=0.0 step=0.1 get=fleet_ship_mass set=set_fleet_ship_mass/>
+                                </div>
+                            </Col>
+                            <Col md=4>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Number of Ships"</div>
+                                    <NumberInput min=1.0 step=1.0 get=fleet_ship_count set=set_fleet_ship_count/>
+                                </div>
+                            </Col>
+                            <Col md=4>
+                                <div style="width: 100%;">
+                                    <div style="margin-bottom: 5px;">"Hours Before Return"</div>
+                                    <NumberInput min=0.0 step=1.0 get=fleet_hours_before_return set=set_fleet_hours_before_return/>
+                                </div>
+                            </Col>
+                        </Row>
+                    })}
                     <Row>
                         <Col md=12>
                             <div style="width: 100%;">
@@ -366,6 +1122,84 @@ pub fn App() -> impl IntoView {
                     </Row>
                 </Grid>
 
+                {move || match routes.get() {
+                    Ok(routes) if routes.len() > 1 => view! {
+                        <table id="alternativetable">
+                            <thead>
+                                <tr>
+                                    <th>"Route"</th>
+                                    <th>"Jumps"</th>
+                                    <th>"Cost"</th>
+                                    <th>"Wormholes"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {routes.into_iter().enumerate().map(|(index, route)| {
+                                    view! {
+                                        <tr class:selected=move || selected_route.get() == index on:click=move |_| set_selected_route.set(index)>
+                                            <td>{ index + 1 }</td>
+                                            <td>{ route.jumps() }</td>
+                                            <td>{ route.cost() }</td>
+                                            <td>{ wormhole_summary(&route) }</td>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }.into_view(),
+                    _ => ().into_view()
+                }}
+
+                {move || {
+                    let collapsed = collapsed_wormholes.get();
+                    (!collapsed.is_empty()).then(|| {
+                        let name = |id : u32| system_name(id).unwrap_or(id.to_string());
+                        let holes = collapsed.iter().map(|c| match &c.signature {
+                            Some(signature) => format!("{} {} - {}", name(c.from_system), signature, name(c.to_system)),
+                            None => format!("{} - {}", name(c.from_system), name(c.to_system))
+                        }).collect::<Vec<_>>().join(", ");
+                        let change = match (uncollapsed_route.get(), route.get()) {
+                            (Some(before), Ok(after)) => format!("The route goes from {} to {} jumps.", before.jumps(), after.jumps()),
+                            (Some(before), Err(_)) => format!("The {} jump route is lost.", before.jumps()),
+                            (None, _) => String::new()
+                        };
+                        view! {
+                            <Alert variant=AlertVariant::Info title=move || view! { "Simulated Collapse" }.into_view()>
+                                { format!("Routing as if {} had collapsed. {} ", holes, change) }
+                                <leptonic-link>
+                                    <a on:click=move |_| set_collapsed_wormholes.set(vec![])>"Restore"</a>
+                                </leptonic-link>
+                            </Alert>
+                        }
+                    })
+                }}
+
+                {move || stale_sources.get().into_iter().map(|(name, e, update_time, cached)| {
+                    let stored = if cached { "stored " } else { "" };
+                    let data = match update_time {
+                        Some(t) => format!("Routes use {}{} data from {} ago", stored, name, hhmmss(Utc::now().naive_utc() - t)),
+                        None => format!("Routes use no {} data", name)
+                    };
+                    let Some(e) = e else {
+                        return view! {
+                            <Alert variant=AlertVariant::Warn title=move || view! { "Stale Data" }.into_view()>
+                                { format!("{} until it refreshes.", data) }
+                            </Alert>
+                        }
+                    };
+                    let login = e.login_url().map(|url| view! {
+                        " "
+                        <LinkExt href=url.to_owned() target=LinkExtTarget::Blank>{ format!("Log in to {}", name) }</LinkExt>
+                        " and it will catch up on the next refresh."
+                    });
+                    view! {
+                        <Alert variant=AlertVariant::Warn title=move || view! { "Stale Data" }.into_view()>
+                            { format!("{}. {}: {}.", data, name, e) }
+                            {login}
+                        </Alert>
+                    }
+                }).collect_view()}
+
                 {move || match route.get() {
                     Err(err) => match err.category {
                         ErrorCategory::Loading => view! { <Alert variant=AlertVariant::Info title=move || view! { "Loading" }.into_view() >{err.description}</Alert> }.into_view(),
@@ -374,25 +1208,62 @@ pub fn App() -> impl IntoView {
                         ErrorCategory::Critical => view! { <Alert variant=AlertVariant::Danger title=move || view! { "Critical Error" }.into_view() >{err.description}</Alert> }.into_view()
                     },
                     Ok(values) => view! {
+                        {values.speculative().then(|| view! {
+                            <Alert variant=AlertVariant::Info title=move || view! { "Speculative Route" }.into_view()>
+                                "This route ends through a signature nobody has scouted yet. The system on the other side is only known by its class."
+                            </Alert>
+                        })}
                         <table id="routetable">
                             <thead>
                                 <tr>
                                     <th>"System"</th>
+                                    <th>"Region"</th>
+                                    <th>"ETA"</th>
                                     <th>"Class"</th>
                                     <th>"Signature"</th>
+                                    <th>"Type"</th>
                                     <th>"Life"</th>
+                                    <th>"Expires"</th>
                                     <th>"Mass"</th>
                                     <th>"Jump Mass"</th>
                                     <th>"Source"</th>
+                                    <th>"Distance"</th>
+                                    <th>"Fuel"</th>
+                                    <th>"Fatigue"</th>
+                                    <th>"Fleet"</th>
+                                    <th>"Cost"</th>
                                     <th>"Actions"</th>
                                 </tr>
                             </thead>
                             <tbody>
-                                {values.into_iter().map(|(system, connection) : (System, Connection)| {
+                                {
+                                let stops = values.stops.clone();
+                                let mut previous_leg = Option::<usize>::None;
+                                let mut previous_system_id = stops[0].id;
+                                values.hops.clone().into_iter().map(|RouteHop { system, connection, cost, mass_verdict, leg, eta, fallbacks }| {
                                     let avoid_system_clone = system.clone();
+                                    let collapse = match &connection {
+                                        Connection::Wormhole(wormhole) => Some(CollapsedWormhole::new(previous_system_id, system.id, wormhole)),
+                                        _ => None
+                                    };
+                                    previous_system_id = system.id;
+                                    let leg_header = (stops.len() > 2 && previous_leg != Some(leg)).then(|| view! {
+                                        <tr class="leg">
+                                            <td colspan="17">{ format!("Leg {}: {} to {}", leg + 1, stops[leg].name, stops[leg + 1].name) }</td>
+                                        </tr>
+                                    });
+                                    previous_leg = Some(leg);
+                                    let fallback_row = (!fallbacks.is_empty()).then(|| view! {
+                                        <tr class="fallback">
+                                            <td colspan="17">{ format!("Fallback: {}", fallbacks.iter().map(connection_summary).collect::<Vec<_>>().join(", ")) }</td>
+                                        </tr>
+                                    });
                                     view! {
+                                        {leg_header}
                                         <tr>
                                             <td>{ system.name }</td>
+                                            <td>{ system.region_name }</td>
+                                            <td>{ eta.map(|t| t.format("%H:%M").to_string()).unwrap_or(" ".to_owned()) }</td>
                                             {
                                                 let name = match system.class {
                                                     SystemClass::C1 => "C1",
@@ -425,12 +1296,20 @@ pub fn App() -> impl IntoView {
                                                 Connection::Wormhole(wormhole) => {
                                                     view! {
                                                         <td>{ wormhole.signature.unwrap_or("???".to_owned())[..3].to_owned() }</td>
+                                                        <td>{ wormhole_type_label(&wormhole) }</td>
                                                         {
                                                             match wormhole.life {
                                                                 WormholeLife::Stable => view! { <td>"Stable"</td> }.into_view(),
                                                                 WormholeLife::EOL => view! { <td class="red">"EOL"</td> }.into_view()
                                                             }
                                                         }
+                                                        {
+                                                            match wormhole.expiry {
+                                                                None => view! { <td>"???"</td> }.into_view(),
+                                                                Some(expiry) if eta.is_some_and(|t| t >= expiry.likely()) => view! { <td class="red">{ expires_in(&expiry, Utc::now().naive_utc()) }</td> }.into_view(),
+                                                                Some(expiry) => view! { <td>{ expires_in(&expiry, Utc::now().naive_utc()) }</td> }.into_view(),
+                                                            }
+                                                        }
                                                         {
                                                             match wormhole.mass {
                                                                 WormholeMass::Stable => view! { <td>"Stable"</td> }.into_view(),
@@ -439,17 +1318,21 @@ pub fn App() -> impl IntoView {
                                                             }
                                                         }
                                                         {
-                                                            match wormhole.jump_mass {
-                                                                None => view! { <td>"???"</td> }.into_view(),
-                                                                Some(v) => view! { <td>{ format!("{}", v) }</td> }.into_view(),
+                                                            match (wormhole.jump_mass, wormhole.jump_mass_bound) {
+                                                                (Some(v), _) => view! { <td>{ format!("{}", v) }</td> }.into_view(),
+                                                                (None, Some(v)) => view! { <td class="orange">{ format!("≤ {}", v) }</td> }.into_view(),
+                                                                (None, None) => view! { <td>"???"</td> }.into_view(),
                                                             }
                                                         }
                                                         {
-                                                            match wormhole.source {
-                                                                WormholeSource::Tripwire => view! { <td>"Tripwire"</td> }.into_view(),
-                                                                WormholeSource::EveScout => view! { <td>"EvE-Scout"</td> }.into_view(),
+                                                            match wormhole.confidence < 1.0 {
+                                                                true => view! { <td class="orange">{ format!("{} ({:.0}%)", source_label(&wormhole.source), wormhole.confidence * 100.0) }</td> }.into_view(),
+                                                                false => view! { <td>{ source_label(&wormhole.source) }</td> }.into_view()
                                                             }
                                                         }
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
                                                     }
                                                 },
                                                 Connection::Gate => {
@@ -459,11 +1342,42 @@ pub fn App() -> impl IntoView {
                                                         <td>" "</td>
                                                         <td>" "</td>
                                                         <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                    }
+                                                },
+                                                Connection::Jump(jump) => {
+                                                    view! {
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>" "</td>
+                                                        <td>{ format!("{:.2} LY", jump.light_years) }</td>
+                                                        <td>{ format!("{}", jump.fuel) }</td>
+                                                        <td>{ hhmm(Duration::seconds((jump.fatigue * 60.0) as i64)) }</td>
                                                     }
                                                 }
                                             }.into_view()
                                             }
+                                            {
+                                                match mass_verdict {
+                                                    None => view! { <td>" "</td> }.into_view(),
+                                                    Some(MassVerdict::Safe) => view! { <td class="green">"Safe"</td> }.into_view(),
+                                                    Some(MassVerdict::Risky) => view! { <td class="orange">"Risky"</td> }.into_view(),
+                                                    Some(MassVerdict::Collapse) => view! { <td class="red">"Collapse"</td> }.into_view(),
+                                                    Some(MassVerdict::TooHeavy) => view! { <td class="red">"Too Heavy"</td> }.into_view(),
+                                                    Some(MassVerdict::Unknown) => view! { <td>"???"</td> }.into_view(),
+                                                }
+                                            }
+                                            <td>{ cost }</td>
                                             <td>
+                                                {(!is_lead_system(system.id)).then(|| view! {
                                                 <leptonic-link>
                                                     <a on:click=move |_| { 
                                                         let mut new_avoid_systems : Vec<System> = avoid_systems.get().clone();
@@ -471,17 +1385,173 @@ pub fn App() -> impl IntoView {
                                                         set_avoid_systems.set(new_avoid_systems);
                                                     }>"Avoid"</a>
                                                 </leptonic-link>" | "
+                                                {collapse.map(|collapse| view! {
+                                                    <leptonic-link>
+                                                        <a on:click=move |_| set_collapsed_wormholes.update(|c| c.push(collapse.clone()))>"Simulate Collapse"</a>
+                                                    </leptonic-link>" | "
+                                                })}
                                                 <LinkExt href={ format!("https://zkillboard.com/system/{}/", system.id) } target=LinkExtTarget::Blank>
                                                     "zKillboard"
                                                 </LinkExt>
+                                                })}
                                             </td>
                                         </tr>
+                                        {fallback_row}
                                     }
-                                }).collect_view()}
+                                }).collect_view()
+                                }
                             </tbody>
+                            <tfoot>
+                                <tr>
+                                    <td colspan="15">"Total"</td>
+                                    <td>{ values.cost() }</td>
+                                    <td>" "</td>
+                                </tr>
+                            </tfoot>
                         </table>
                     }.into_view(),
                 }}
+
+                {move || {
+                    let leads = leads.get();
+                    (!leads.is_empty()).then(|| view! {
+                        <table id="leadtable">
+                            <thead>
+                                <tr>
+                                    <th>"Lead"</th>
+                                    <th>"System"</th>
+                                    <th>"Signature"</th>
+                                    <th>"Type"</th>
+                                    <th>"Leads To"</th>
+                                    <th>"Chance"</th>
+                                    <th>"Expected Saving"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {leads.into_iter().enumerate().map(|(index, lead)| view! {
+                                    <tr>
+                                        <td>{ index + 1 }</td>
+                                        <td>{ system_label(&lead.system) }</td>
+                                        <td>{ lead.wormhole.signature.clone().unwrap_or("???".to_owned())[..3].to_owned() }</td>
+                                        <td>{ wormhole_type_label(&lead.wormhole) }</td>
+                                        <td>{ format!("{:?}", lead.class) }</td>
+                                        <td>{ format!("{:.0}%", lead.chance * 100.0) }</td>
+                                        <td>{ format!("{:.1}", lead.saving) }</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    })
+                }}
+
+                {move || {
+                    let connections = manual_connections.get();
+                    let name = move |id : u32| system_name(id).unwrap_or(id.to_string());
+                    (!connections.is_empty()).then(|| view! {
+                        <table id="manualtable">
+                            <thead>
+                                <tr>
+                                    <th>"From"</th>
+                                    <th>"To"</th>
+                                    <th>"Signature"</th>
+                                    <th>"Type"</th>
+                                    <th>"Life"</th>
+                                    <th>"Mass"</th>
+                                    <th>"Expires"</th>
+                                    <th>"Actions"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {connections.into_iter().enumerate().map(|(index, connection)| view! {
+                                    <tr>
+                                        <td>{ name(connection.from_system) }</td>
+                                        <td>{ name(connection.to_system) }</td>
+                                        <td>{ connection.signature.clone().unwrap_or("???".to_owned()) }</td>
+                                        <td>{ connection.wormhole_type.clone().unwrap_or("???".to_owned()) }</td>
+                                        <td>{ format!("{:?}", connection.life) }</td>
+                                        <td>{ format!("{:?}", connection.mass) }</td>
+                                        <td>{ hhmm(connection.expires - Utc::now().naive_utc()) }</td>
+                                        <td>
+                                            <leptonic-link>
+                                                <a on:click=move |_| {
+                                                    let mut connections = manual_connections.get();
+                                                    connections.remove(index);
+                                                    save_manual_connections(connections);
+                                                }>"Remove"</a>
+                                            </leptonic-link>
+                                        </td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    })
+                }}
+
+                {move || show_diagnostics.get().then(|| {
+                    let rejected = rejected.get();
+                    let label = move |v : SystemOrClass| match v {
+                        SystemOrClass::SpecificSystem(id) => match system_name(id) {
+                            Some(name) => format!("{} ({})", name, id),
+                            None => id.to_string()
+                        },
+                        v => format!("{:?}", v)
+                    };
+
+                    let mut counts = Vec::<(WormholeSource, RejectReason, usize)>::new();
+                    for r in &rejected {
+                        match counts.iter_mut().find(|(source, reason, _)| *source == r.source && *reason == r.reason) {
+                            Some((_, _, count)) => *count += 1,
+                            None => counts.push((r.source.clone(), r.reason.clone(), 1))
+                        }
+                    }
+
+                    view! {
+                        <table id="diagnosticcounttable">
+                            <thead>
+                                <tr>
+                                    <th>"Source"</th>
+                                    <th>"Reason"</th>
+                                    <th>"Count"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {counts.into_iter().map(|(source, reason, count)| view! {
+                                    <tr>
+                                        <td>{ source_label(&source) }</td>
+                                        <td>{ reason.to_string() }</td>
+                                        <td>{ count }</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                        <table id="diagnosticstable">
+                            <thead>
+                                <tr>
+                                    <th>"Source"</th>
+                                    <th>"Reason"</th>
+                                    <th>"Record"</th>
+                                    <th>"From"</th>
+                                    <th>"From Signature"</th>
+                                    <th>"To"</th>
+                                    <th>"To Signature"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {rejected.into_iter().map(|r| view! {
+                                    <tr>
+                                        <td>{ source_label(&r.source) }</td>
+                                        <td>{ r.reason.to_string() }</td>
+                                        <td>{ r.record_id.unwrap_or("???".to_owned()) }</td>
+                                        <td>{ label(r.from_system) }</td>
+                                        <td>{ r.from_signature.unwrap_or("???".to_owned()) }</td>
+                                        <td>{ label(r.to_system) }</td>
+                                        <td>{ r.to_signature.unwrap_or("???".to_owned()) }</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }
+                })}
             </div>
         </Root>
     }
@@ -494,7 +1564,14 @@ fn main() {
             .set_max_level(tracing::Level::TRACE)
             .build(),
     );
-    mount_to_body(|| {
-        view! { <App/>}
+    spawn_local(async {
+        match get_config().await.and_then(|config| Ok((config.clone(), get_providers(&config)?))) {
+            Ok((config, providers)) => mount_to_body(move || view! { <App config=config providers=providers/> }),
+            Err(e) => mount_to_body(move || view! {
+                <Root default_theme=LeptonicTheme::default()>
+                    <Alert variant=AlertVariant::Danger title=move || view! { "Critical Error" }.into_view()>{e.clone()}</Alert>
+                </Root>
+            })
+        }
     });
 }
@@ -0,0 +1,103 @@
+use std::future::ready;
+use chrono::{NaiveDateTime, Utc, Duration};
+use serde::{Deserialize, Serialize};
+use eve_route::{WormholeLife, WormholeMass, WormholeSide, WormholeSource};
+
+use crate::cache::local_storage;
+use crate::provider::*;
+use crate::tripwire::SystemOrClass;
+
+const MANUAL_STORAGE_KEY : &str = "journey-planner-manual-connections";
+const MANUAL_REFRESH_MILLIS : u64 = 1000;
+
+// A wormhole entered by hand, kept in the browser until it expires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManualConnection {
+    pub from_system : u32,
+    pub to_system : u32,
+    pub signature : Option<String>, // In from_system
+    pub wormhole_type : Option<String>,
+    pub life : WormholeLife,
+    pub mass : WormholeMass,
+    pub added : NaiveDateTime,
+    pub expires : NaiveDateTime
+}
+
+impl ManualConnection {
+    pub fn new(from_system : u32, to_system : u32, signature : Option<String>, wormhole_type : Option<String>, life : WormholeLife, mass : WormholeMass, hours : u32) -> Self {
+        let added = Utc::now().naive_utc();
+        ManualConnection { from_system, to_system, signature, wormhole_type, life, mass, added, expires : added + Duration::hours(hours as i64) }
+    }
+}
+
+// Expired connections are dropped as they're read
+pub fn get_manual_connections() -> Result<Vec<ManualConnection>, String> {
+    let Some(json) = local_storage()?.get_item(MANUAL_STORAGE_KEY).map_err(|_| format!("Cannot read manual connections"))? else { return Ok(vec![]) };
+    let now = Utc::now().naive_utc();
+    Ok(serde_json::from_str::<Vec<ManualConnection>>(&json)
+        .map_err(|e| format!("Failed to parse manual connections: {:?}", e))?
+        .into_iter()
+        .filter(|c| c.expires > now)
+        .collect())
+}
+
+pub fn set_manual_connections(connections : &[ManualConnection]) -> Result<(), String> {
+    let json = serde_json::to_string(connections).map_err(|_| format!("Cannot save manual connections"))?;
+    local_storage()?.set_item(MANUAL_STORAGE_KEY, &json).map_err(|_| format!("Cannot save manual connections"))
+}
+
+#[derive(Debug, Clone)]
+pub struct ManualProvider;
+
+impl ConnectionProvider for ManualProvider {
+    fn name(&self) -> String {
+        "Manual".to_owned()
+    }
+
+    fn refresh_millis(&self) -> u64 {
+        MANUAL_REFRESH_MILLIS
+    }
+
+    fn fetch(&self, _ : Option<Connections>) -> ConnectionsFuture {
+        Box::pin(ready(get_manual()))
+    }
+
+    // Already in browser storage and read without waiting
+    fn keeps_snapshot(&self) -> bool { false }
+}
+
+fn get_manual() -> Result<Connections, ProviderError> {
+    let connections = get_manual_connections().map_err(ProviderError::Request)?;
+
+    let wormholes = connections.iter().map(|c| {
+        // As in Tripwire, a K162 type means the typed end is the other one
+        let (wormhole_type, from_side) = match c.wormhole_type.as_deref() {
+            Some("K162") => (None, WormholeSide::K162),
+            Some(t) => (Some(t.to_owned()), WormholeSide::Origin),
+            None => (None, WormholeSide::Unknown)
+        };
+        ProvidedWormhole {
+            from_system : c.from_system,
+            to_system : SystemOrClass::SpecificSystem(c.to_system),
+            from_signature : c.signature.clone(),
+            to_signature : None,
+            wormhole_type,
+            from_side,
+            life : c.life.clone(),
+            mass : c.mass.clone(),
+            age : WormholeAge::RemainingHours(c.added, (c.expires - c.added).num_hours() as u32),
+            source : WormholeSource::Manual,
+            record_id : None,
+            first_seen : c.added,
+            updated : c.added,
+            confidence : 1.0
+        }
+    }).collect();
+
+    Ok(Connections {
+        wormholes,
+        rejected : vec![],
+        signature_count : connections.len(),
+        signature_time : connections.iter().map(|c| c.added).max().unwrap_or(NaiveDateTime::UNIX_EPOCH)
+    })
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use chrono::{NaiveDateTime, Duration};
+use serde::{Deserialize, Serialize};
+use eve_route::{WormholeLife, WormholeMass, WormholeSide, WormholeSource, update_confidence};
+
+use crate::config::Config;
+use crate::tripwire::*;
+use crate::eve_scout::*;
+use crate::manual::*;
+
+// How much life a wormhole has left, in whatever form its source gives it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum WormholeAge {
+    Since(NaiveDateTime), // First seen, so the type's lifetime gives the expiry
+    RemainingHours(NaiveDateTime, u32) // Hours left as of when they were fetched
+}
+
+// A wormhole from any source, before it's added to the graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProvidedWormhole {
+    pub from_system : u32,
+    pub to_system : SystemOrClass,
+    pub from_signature : Option<String>,
+    pub to_signature : Option<String>,
+    pub wormhole_type : Option<String>,
+    pub from_side : WormholeSide, // End of the wormhole in from_system
+    pub life : WormholeLife,
+    pub mass : WormholeMass,
+    pub age : WormholeAge,
+    pub source : WormholeSource,
+    pub record_id : Option<String>, // Id of the wormhole in its source, when it has one
+    pub first_seen : NaiveDateTime,
+    pub updated : NaiveDateTime, // Last changed in its source
+    pub confidence : f64 // From the source's policy
+}
+
+impl ProvidedWormhole {
+    pub fn reject(&self, reason : RejectReason) -> RejectedWormhole {
+        RejectedWormhole {
+            reason,
+            source : self.source.clone(),
+            record_id : self.record_id.clone(),
+            from_system : SystemOrClass::SpecificSystem(self.from_system),
+            to_system : self.to_system,
+            from_signature : self.from_signature.clone(),
+            to_signature : self.to_signature.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RejectReason {
+    TooOld, // First seen longer ago than the source's maximum age
+    NoSignatures, // Probably created by a deathclone
+    Gate, // Already in the static data
+    FromUnknown, // The first side isn't a known system
+    ToUnknown, // The far side isn't scouted and has no class
+    NotInSde
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RejectReason::TooOld => write!(f, "Older than the source's maximum age"),
+            RejectReason::NoSignatures => write!(f, "No signature on either side"),
+            RejectReason::Gate => write!(f, "Gate"),
+            RejectReason::FromUnknown => write!(f, "First side not a known system"),
+            RejectReason::ToUnknown => write!(f, "Far side has no system or class"),
+            RejectReason::NotInSde => write!(f, "System not in the static data")
+        }
+    }
+}
+
+// A wormhole left out of the graph, with the raw ids to find it in its source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RejectedWormhole {
+    pub reason : RejectReason,
+    pub source : WormholeSource,
+    pub record_id : Option<String>,
+    pub from_system : SystemOrClass,
+    pub to_system : SystemOrClass,
+    pub from_signature : Option<String>,
+    pub to_signature : Option<String>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Connections {
+    pub wormholes : Vec<ProvidedWormhole>,
+    pub rejected : Vec<RejectedWormhole>,
+    pub signature_count : usize,
+    pub signature_time : NaiveDateTime
+}
+
+// Sources say when anything last changed, but their policy can still leave out or
+// distrust wormholes as time passes
+impl PartialEq for Connections {
+    fn eq(&self, other: &Connections) -> bool {
+        self.signature_time.eq(&other.signature_time) && self.signature_count.eq(&other.signature_count)
+            && self.rejected.len() == other.rejected.len()
+            && self.wormholes.iter().map(|w| w.confidence).eq(other.wormholes.iter().map(|w| w.confidence))
+    }
+}
+
+// Kept apart so each can say what to do about it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    Auth { login_url : String }, // Session expired or never logged in
+    Server(u16), // HTTP status
+    RateLimited,
+    Schema(String), // Answered, but not in the expected shape
+    Request(String) // Never answered, or could not be read
+}
+
+impl ProviderError {
+    pub fn login_url(&self) -> Option<&str> {
+        match self {
+            ProviderError::Auth { login_url } => Some(login_url),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProviderError::Auth { .. } => write!(f, "Not logged in, the session may have expired"),
+            ProviderError::Server(status) => write!(f, "Server error {}", status),
+            ProviderError::RateLimited => write!(f, "Rate limited, will keep retrying"),
+            ProviderError::Schema(e) => write!(f, "Unexpected response: {}", e),
+            ProviderError::Request(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+// Sorts a response that isn't a success by its status
+pub fn status_error(status : reqwest::StatusCode, login_url : Option<String>) -> ProviderError {
+    match (status.as_u16(), login_url) {
+        (401 | 403, Some(login_url)) => ProviderError::Auth { login_url },
+        (429, _) => ProviderError::RateLimited,
+        (status, _) => ProviderError::Server(status)
+    }
+}
+
+pub type ConnectionsFuture = Pin<Box<dyn Future<Output = Result<Connections, ProviderError>>>>;
+
+// A source of wormholes for the graph. Each is refreshed on its own interval.
+pub trait ConnectionProvider {
+    fn name(&self) -> String;
+    fn refresh_millis(&self) -> u64;
+
+    // Gives back previous when the source has nothing new
+    fn fetch(&self, previous : Option<Connections>) -> ConnectionsFuture;
+
+    // Applies the source's policy again to connections from an earlier fetch, as time passes
+    fn apply_policy(&self, connections : Connections, _ : NaiveDateTime) -> Connections { connections }
+
+    // Whether the last connections are stored for the next visit, to route with until the first fetch
+    fn keeps_snapshot(&self) -> bool { true }
+}
+
+// Which of a source's wormholes to keep and how far to trust them. Fields
+// left unset take the defaults for the kind of source.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default)]
+pub struct SourcePolicy {
+    pub max_age_hours : Option<f64>, // Since first seen
+    pub use_remaining_life : Option<bool>, // Take life from the source's own hours remaining, when it has them
+    pub distrust_after_hours : Option<f64>, // Confidence halves for each of these without an update, once the first has passed
+    pub drop_unsigned : Option<bool> // No signature on either side, usually made by a deathclone
+}
+
+impl SourcePolicy {
+    pub fn tripwire() -> Self {
+        SourcePolicy { max_age_hours : Some(24.0), use_remaining_life : Some(false), distrust_after_hours : None, drop_unsigned : Some(true) }
+    }
+
+    pub fn eve_scout() -> Self {
+        SourcePolicy { max_age_hours : None, use_remaining_life : Some(true), distrust_after_hours : None, drop_unsigned : Some(false) }
+    }
+
+    pub fn or(self, defaults : SourcePolicy) -> SourcePolicy {
+        SourcePolicy {
+            max_age_hours : self.max_age_hours.or(defaults.max_age_hours),
+            use_remaining_life : self.use_remaining_life.or(defaults.use_remaining_life),
+            distrust_after_hours : self.distrust_after_hours.or(defaults.distrust_after_hours),
+            drop_unsigned : self.drop_unsigned.or(defaults.drop_unsigned)
+        }
+    }
+
+    pub fn too_old(&self, first_seen : NaiveDateTime, now : NaiveDateTime) -> bool {
+        self.max_age_hours.is_some_and(|h| now - first_seen > hours(h))
+    }
+
+    // Rounded to a percent, so a quiet source only changes as trust is lost
+    pub fn confidence(&self, updated : NaiveDateTime, now : NaiveDateTime) -> f64 {
+        (update_confidence(updated, now, self.distrust_after_hours.map(hours)) * 100.0).round() / 100.0
+    }
+
+    // Leaves out wormholes that have grown too old and updates how far the rest are trusted
+    pub fn apply(&self, connections : Connections, now : NaiveDateTime) -> Connections {
+        let (too_old, wormholes) : (Vec<_>, Vec<_>) = connections.wormholes.into_iter().partition(|w| self.too_old(w.first_seen, now));
+        let mut rejected = connections.rejected;
+        rejected.extend(too_old.iter().map(|w| w.reject(RejectReason::TooOld)));
+        let wormholes = wormholes.into_iter().map(|w| ProvidedWormhole { confidence : self.confidence(w.updated, now), ..w }).collect();
+        Connections { wormholes, rejected, ..connections }
+    }
+}
+
+// Ten years, far beyond any wormhole, and well inside what a Duration can hold
+const MAX_POLICY_HOURS : f64 = 87_600.0;
+
+// Clamped, so a wild value in config.json can't overflow a Duration
+fn hours(hours : f64) -> Duration {
+    Duration::seconds((hours.clamp(0.0, MAX_POLICY_HOURS) * 3600.0) as i64)
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ProviderConfig {
+    Tripwire {
+        name : Option<String>,
+        url : Option<String>, // Defaults to tripwire_url
+        #[serde(default)]
+        credentials : bool,
+        refresh_seconds : Option<u64>,
+        #[serde(default)]
+        policy : SourcePolicy
+    },
+    EveScout {
+        name : Option<String>,
+        url : Option<String>, // Defaults to eve_scout_url
+        refresh_seconds : Option<u64>,
+        #[serde(default)]
+        policy : SourcePolicy
+    }
+}
+
+const TRIPWIRE_REFRESH_MILLIS : u64 = 5000;
+const EVE_SCOUT_REFRESH_MILLIS : u64 = 30000;
+
+// Without a providers list, one Tripwire and EvE-Scout, as configured by the other fields.
+// Manually entered connections always come last.
+pub fn get_providers(config : &Config) -> Result<Vec<Rc<dyn ConnectionProvider>>, String> {
+    let provider_configs = config.providers.clone().unwrap_or(vec![
+        ProviderConfig::Tripwire { name : None, url : None, credentials : config.tripwire_credentials, refresh_seconds : None, policy : SourcePolicy::default() },
+        ProviderConfig::EveScout { name : None, url : None, refresh_seconds : None, policy : SourcePolicy::default() }
+    ]);
+
+    let mut providers = provider_configs.into_iter().map(|provider_config| -> Result<Rc<dyn ConnectionProvider>, String> {
+        Ok(match provider_config {
+            ProviderConfig::Tripwire { name, url, credentials, refresh_seconds, policy } => Rc::new(TripwireProvider {
+                name : name.unwrap_or("Tripwire".to_owned()),
+                url : match url { Some(url) => url.trim_end_matches('/').to_owned(), None => config.tripwire_url()? },
+                credentials,
+                refresh_millis : refresh_seconds.map_or(TRIPWIRE_REFRESH_MILLIS, |s| s * 1000),
+                policy : policy.or(SourcePolicy::tripwire())
+            }),
+            ProviderConfig::EveScout { name, url, refresh_seconds, policy } => Rc::new(EveScoutProvider {
+                name : name.unwrap_or("EvE-Scout".to_owned()),
+                url : match url { Some(url) => url, None => config.eve_scout_url()? },
+                refresh_millis : refresh_seconds.map_or(EVE_SCOUT_REFRESH_MILLIS, |s| s * 1000),
+                policy : policy.or(SourcePolicy::eve_scout())
+            })
+        })
+    }).collect::<Result<Vec<_>, String>>()?;
+
+    providers.push(Rc::new(ManualProvider));
+    Ok(providers)
+}
@@ -6,11 +6,16 @@ use leptos_use::{use_interval, UseIntervalReturn};
 #[derive(Debug, Clone)]
 pub struct Tracker<E> {
     pub update_time : Option<NaiveDateTime>,
-    pub update_error : Option<E>
+    pub update_error : Option<E>,
+    pub cached : bool // Still showing the initial value, from an earlier visit
 }
 
+// Refetches every millis while enabled. Disabled, the last value is kept but not refreshed.
+// The initial value and its time stand in until the first fetch succeeds.
 pub fn create_tracked_local_resource<T, E, Fu>(
     millis : u64,
+    enabled : Signal<bool>,
+    initial : Option<(T, NaiveDateTime)>,
     fetcher: impl Fn(Option<T>) -> Fu + Copy + 'static
 ) -> (Memo<Option<T>>, Signal<Tracker<E>>)
 where
@@ -19,7 +24,12 @@ where
     Fu: Future<Output = Result<T, E>> + 'static,
 {
     let (refresh, set_refresh) = create_signal(0u64);
-    let previous_result = store_value::<(Option<T>,Tracker<E>)>((None, Tracker { update_time : None, update_error: None }));
+    let initial = match initial {
+        Some((v, time)) => (Some(v), Tracker { update_time : Some(time), update_error : None, cached : true }),
+        None => (None, Tracker { update_time : None, update_error : None, cached : false })
+    };
+    let previous_result = store_value::<(Option<T>,Tracker<E>)>(initial.clone());
+    let initial = store_value(initial);
 
     let resource = create_local_resource(move || { refresh.get() }, async move |_| {
         // This closure is not reentrant. StoredValue::get_value/set_value
@@ -27,9 +37,12 @@ where
         // a RefCell, which means it will panic when called in parallel.
 
         let (previous_value, previous_tracker) = previous_result.get_value();
-        let result = match fetcher(previous_value.clone()).await {
-            Ok(v) => (Some(v), Tracker { update_time : Some(Utc::now().naive_utc()), update_error : None }),
-            Err(e) => (previous_value, Tracker { update_time : previous_tracker.update_time, update_error : Some(e) })
+        let result = match enabled.get_untracked() {
+            false => (previous_value, previous_tracker),
+            true => match fetcher(previous_value.clone()).await {
+                Ok(v) => (Some(v), Tracker { update_time : Some(Utc::now().naive_utc()), update_error : None, cached : false }),
+                Err(e) => (previous_value, Tracker { update_time : previous_tracker.update_time, update_error : Some(e), cached : previous_tracker.cached })
+            }
         };
         previous_result.set_value(result.clone());
         set_timeout(move || { set_refresh.update(|v| *v += 1); }, std::time::Duration::from_millis(millis));
@@ -37,14 +50,14 @@ where
     });
 
     let memo = create_memo(move |_|  {
-        resource.get().map(|(v, _)| v).flatten()
+        resource.get().map_or_else(|| initial.with_value(|(v, _)| v.clone()), |(v, _)| v)
     });
 
     let UseIntervalReturn { counter : tracker_counter, .. } = use_interval(1000);
 
     let tracker = Signal::derive(move ||  {
         let _ = tracker_counter.get();
-        resource.get().map(|(_, v)| v).unwrap_or(Tracker { update_time : None, update_error: None })
+        resource.get().map_or_else(|| initial.with_value(|(_, t)| t.clone()), |(_, t)| t)
     });
 
     (memo, tracker)
@@ -1,10 +1,13 @@
 use std::collections::HashMap;
 use std::convert::From;
-use chrono::{NaiveDateTime, Utc, Duration};
-use web_sys;
+use chrono::{NaiveDateTime, Utc};
 use tracing::info;
-use serde::{de::Error, Deserialize, Deserializer};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
 use serde_json;
+use eve_sde::SystemClass;
+use eve_route::{WormholeLife, WormholeMass, WormholeSide, WormholeSource};
+
+use crate::provider::*;
 
 fn deserialize_system_id<'de, D>(deserializer: D) -> Result<SystemOrClass, D::Error> where D: Deserializer<'de> {
     let s: Option<&str> = Deserialize::deserialize(deserializer)?;
@@ -41,6 +44,10 @@ pub struct TripwireWormholeRaw {
     #[serde(alias = "type")]
     wormhole_type : Option<String>,
 
+    // Which signature the type was recorded on, "initial" or "secondary"
+    #[serde(default)]
+    parent : Option<String>,
+
     life : WormholeLife,
     mass : WormholeMass,
 }
@@ -71,40 +78,7 @@ pub struct TripwireRaw {
     pub wormholes : Option<HashMap<String,TripwireWormholeRaw>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize)]
-pub enum WormholeLife {
-    #[serde(alias = "stable")]
-    Stable,
-
-    #[serde(alias = "critical")]
-    EOL,
-}
-
-#[derive(Debug, Clone, PartialEq, Deserialize)]
-pub enum WormholeMass {
-    #[serde(alias = "stable")]
-    Stable,
-
-    #[serde(alias = "destab")]
-    Destab,
-
-    #[serde(alias = "critical")]
-    VOC
-}
-
-#[derive(Debug, Clone)]
-pub struct TripwireWormhole {
-    pub from_system : u32,
-    pub to_system : SystemOrClass,
-    pub from_signature : Option<String>,
-    pub to_signature : Option<String>,
-    pub wormhole_type : Option<String>,
-    pub life_time : NaiveDateTime,
-    pub life : WormholeLife,
-    pub mass : WormholeMass
-}
-
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SystemOrClass {
     SpecificSystem(u32),
     Nullsec,
@@ -147,29 +121,68 @@ impl From<Option<u32>> for SystemOrClass {
     }
 }
 
+impl SystemOrClass {
+    // Class of a side that hasn't been scouted yet, None for a specific or unknown system
+    pub fn class(&self) -> Option<SystemClass> {
+        match self {
+            SystemOrClass::Nullsec => Some(SystemClass::Nullsec),
+            SystemOrClass::Lowsec => Some(SystemClass::Lowsec),
+            SystemOrClass::Highsec => Some(SystemClass::Highsec),
+            SystemOrClass::Class1 => Some(SystemClass::C1),
+            SystemOrClass::Class2 => Some(SystemClass::C2),
+            SystemOrClass::Class3 => Some(SystemClass::C3),
+            SystemOrClass::Class4 => Some(SystemClass::C4),
+            SystemOrClass::Class5 => Some(SystemClass::C5),
+            SystemOrClass::Class6 => Some(SystemClass::C6),
+            SystemOrClass::Class13 => Some(SystemClass::C13),
+            SystemOrClass::Pochven => Some(SystemClass::Pochven),
+            SystemOrClass::SpecificSystem(_) | SystemOrClass::Unknown => None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
-pub struct TripwireRefresh {
-    pub wormholes : Vec::<TripwireWormhole>,
-    pub signature_count : usize,
-    pub signature_time : NaiveDateTime,
+pub struct TripwireProvider {
+    pub name : String,
+    pub url : String, // Base URL, where refresh.php is
+    pub credentials : bool, // Send the session cookie to another origin
+    pub refresh_millis : u64,
+    pub policy : SourcePolicy
 }
 
-impl PartialEq for TripwireRefresh {
-    fn eq(&self, other: &TripwireRefresh) -> bool {
-        self.signature_time.eq(&other.signature_time) && self.signature_count.eq(&other.signature_count)
+impl ConnectionProvider for TripwireProvider {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn refresh_millis(&self) -> u64 {
+        self.refresh_millis
+    }
+
+    fn fetch(&self, previous : Option<Connections>) -> ConnectionsFuture {
+        Box::pin(get_tripwire(self.clone(), previous))
+    }
+
+    // Tripwire gives back the last connections while the map is quiet, so they still age
+    fn apply_policy(&self, connections : Connections, now : NaiveDateTime) -> Connections {
+        self.policy.apply(connections, now)
     }
 }
 
-pub async fn get_tripwire(previous_result : Option<TripwireRefresh>) -> Result<TripwireRefresh, String> {
+pub async fn get_tripwire(provider : TripwireProvider, previous_result : Option<Connections>) -> Result<Connections, ProviderError> {
     let signature_count = previous_result.as_ref().map(|v| v.signature_count).unwrap_or(0);
     let signature_time = previous_result.as_ref().map(|v| v.signature_time).unwrap_or(NaiveDateTime::UNIX_EPOCH);
 
-    let mut data = Vec::<TripwireWormhole>::new();
+    let mut data = Vec::<ProvidedWormhole>::new();
+    let mut rejected = Vec::<RejectedWormhole>::new();
 
-    let baseurl = web_sys::window().ok_or_else(|| format!("Cannot get base URL"))?.origin();
+    let baseurl = &provider.url;
 
     let client = reqwest::Client::new();
-    let result = client.post(format!("{baseurl}/refresh.php"))
+    let mut request = client.post(format!("{baseurl}/refresh.php"));
+    if provider.credentials { request = request.fetch_credentials_include() }
+
+    let response = request
         .form(&HashMap::from([
             ("mode", "refresh".to_owned()),
             ("systemID", "30000142".to_owned()),
@@ -177,26 +190,33 @@ pub async fn get_tripwire(previous_result : Option<TripwireRefresh>) -> Result<T
             ("signatureCount", signature_count.to_string()),
             ("signatureTime", signature_time.format("%Y-%m-%d %H:%M:%S").to_string()),
         ]))
-        .send().await.map_err(|_| format!("Tripwire HTTP request failed"))?
-        .error_for_status().map_err(|_| format!("Tripwire HTTP request failed"))?
-        .bytes().await.map_err(|_| format!("Tripwire HTTP request failed"))?;
+        .send().await.map_err(|_| ProviderError::Request(format!("Tripwire HTTP request failed")))?;
+
+    let login_url = format!("{baseurl}/");
+    if !response.status().is_success() { return Err(status_error(response.status(), Some(login_url))) }
+
+    // Without a session, refresh.php redirects to or answers with the login page. The
+    // content type can't tell, as PHP sends text/html for the JSON too.
+    let redirected = !response.url().path().ends_with("/refresh.php");
+
+    let result = response.bytes().await.map_err(|_| ProviderError::Request(format!("Tripwire HTTP request failed")))?;
+
+    let markup = result.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'<');
+    if redirected || markup { return Err(ProviderError::Auth { login_url }) }
 
     let json = serde_json::from_slice::<TripwireRaw>(&result)
-        .map_err(|e| format!("Tripwire parse failed: {:?}", e))?;
+        .map_err(|e| ProviderError::Schema(format!("Tripwire parse failed: {:?}", e)))?;
 
     let signatures = match json.signatures {
         Some(s) => s,
-        None => {
-            let previous_result_value = previous_result.ok_or_else(|| format!("Tripwire signatures not present in initial refresh"))?;
-            return Ok(TripwireRefresh { wormholes : vec![], signature_count : previous_result_value.signature_count, signature_time: previous_result_value.signature_time });
-        }
+        None => return previous_result.ok_or_else(|| ProviderError::Schema(format!("Tripwire signatures not present in initial refresh")))
     };
 
     let signature_time = signatures
         .iter()
         .map(|(_, v)| NaiveDateTime::parse_from_str(&v.modified_time, "%Y-%m-%d %H:%M:%S"))
         .collect::<Result<Vec<_>,_>>()
-        .map_err(|_| format!("Tripwire modifiedTime format wrong"))?
+        .map_err(|_| ProviderError::Schema(format!("Tripwire modifiedTime format wrong")))?
         .into_iter()
         .max()
         .unwrap_or(NaiveDateTime::UNIX_EPOCH);
@@ -205,37 +225,78 @@ pub async fn get_tripwire(previous_result : Option<TripwireRefresh>) -> Result<T
 
     info!("Signature update: {:?}", signature_time);
 
-    let wormholes = json.wormholes.ok_or_else(|| format!("Tripwire wormholes not present"))?;
+    let wormholes = json.wormholes.ok_or_else(|| ProviderError::Schema(format!("Tripwire wormholes not present")))?;
+
+    let now = Utc::now().naive_utc();
 
     for (wormhole_id, wormhole) in wormholes {
-        let from = signatures.get(&wormhole.initial_id).ok_or_else(|| format!("Tripwire initial signature details missing from {}", wormhole_id))?;
-        let to = signatures.get(&wormhole.secondary_id).ok_or_else(|| format!("Tripwire secondary signature details missing from {}", wormhole_id))?;
+        let from = signatures.get(&wormhole.initial_id).ok_or_else(|| ProviderError::Schema(format!("Tripwire initial signature details missing from {}", wormhole_id)))?;
+        let to = signatures.get(&wormhole.secondary_id).ok_or_else(|| ProviderError::Schema(format!("Tripwire secondary signature details missing from {}", wormhole_id)))?;
+
+        let reject = |reason| RejectedWormhole {
+            reason,
+            source : WormholeSource::Tripwire,
+            record_id : Some(wormhole_id.clone()),
+            from_system : from.system_id,
+            to_system : to.system_id,
+            from_signature : from.signature_id.clone(),
+            to_signature : to.signature_id.clone()
+        };
 
-        let from_system = match from.system_id { SystemOrClass::SpecificSystem(v) => v, _ => continue };
+        let from_system = match from.system_id { SystemOrClass::SpecificSystem(v) => v, _ => { rejected.push(reject(RejectReason::FromUnknown)); continue } };
 
         let life_time = [from.life_time, to.life_time].into_iter().max().unwrap();
-        let age = Utc::now().naive_utc() - life_time;
 
-        // Wormholes older than 24 hours probably don't exist any more
-        if age > Duration::hours(24) { continue }
+        // Too long since first seen for the source's max_age_hours, so probably gone
+        if provider.policy.too_old(life_time, now) { rejected.push(reject(RejectReason::TooOld)); continue }
 
         // Probably created by a deathclone
-        if from.signature_id == None && to.signature_id == None { continue }
+        let unsigned = from.signature_id == None && to.signature_id == None;
+        if unsigned && provider.policy.drop_unsigned.unwrap_or(false) { rejected.push(reject(RejectReason::NoSignatures)); continue }
 
         // Don't want gates, already have then in the static data
-        if wormhole.wormhole_type == Some("GATE".to_owned()) || from.signature_id == Some("GAT".to_owned()) || to.signature_id == Some("GAT".to_owned()) { continue }
-
-        data.push(TripwireWormhole {
+        if wormhole.wormhole_type == Some("GATE".to_owned()) || from.signature_id == Some("GAT".to_owned()) || to.signature_id == Some("GAT".to_owned()) { rejected.push(reject(RejectReason::Gate)); continue }
+
+        let typed_initial = match wormhole.parent.as_deref() {
+            Some("initial") => Some(true),
+            Some("secondary") => Some(false),
+            _ => None
+        };
+
+        // A K162 type names the exit end, so the origin is the other end and its type isn't known
+        let (wormhole_type, typed_initial) = match wormhole.wormhole_type.as_deref() {
+            Some("K162") => (None, typed_initial.map(|v| !v)),
+            _ => (wormhole.wormhole_type, typed_initial)
+        };
+
+        let from_side = match typed_initial {
+            Some(true) => WormholeSide::Origin,
+            Some(false) => WormholeSide::K162,
+            None => WormholeSide::Unknown
+        };
+
+        let updated = [&from.modified_time, &to.modified_time].into_iter()
+            .filter_map(|t| NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S").ok())
+            .max()
+            .unwrap_or(life_time);
+
+        data.push(ProvidedWormhole {
             from_system,
             to_system : to.system_id,
             from_signature : from.signature_id.clone(),
             to_signature : to.signature_id.clone(),
-            wormhole_type : wormhole.wormhole_type,
-            life_time,
+            wormhole_type,
+            from_side,
             life : wormhole.life,
-            mass : wormhole.mass
+            mass : wormhole.mass,
+            age : WormholeAge::Since(life_time),
+            source : WormholeSource::Tripwire,
+            record_id : Some(wormhole_id),
+            first_seen : life_time,
+            updated,
+            confidence : provider.policy.confidence(updated, now)
         });
     }
 
-    Ok(TripwireRefresh {wormholes : data, signature_count, signature_time })
+    Ok(Connections { wormholes : data, rejected, signature_count, signature_time })
 }
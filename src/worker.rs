@@ -0,0 +1,62 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use leptos::*;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{MessageEvent, Worker};
+use tracing::warn;
+use eve_route::{RouteQuery, WorkerRequest, WorkerResponse};
+
+// Copied next to journey.htm, it loads the route_worker binary
+const WORKER_URL : &str = "./route_worker_loader.js";
+
+// The page's end of the routing worker. Messages go both ways as JSON.
+#[derive(Clone)]
+pub struct RouteWorker {
+    worker : Worker,
+    next_id : Rc<Cell<u64>>
+}
+
+impl RouteWorker {
+    pub fn new(on_response : impl Fn(WorkerResponse) + 'static) -> Result<Self, String> {
+        let worker = Worker::new(WORKER_URL).map_err(|_| format!("Cannot start the routing worker"))?;
+
+        let on_message = Closure::<dyn FnMut(MessageEvent)>::new(move |event : MessageEvent| {
+            let Some(json) = event.data().as_string() else { return };
+            match serde_json::from_str::<WorkerResponse>(&json) {
+                Ok(response) => on_response(response),
+                Err(e) => warn!("Failed to parse routing worker message: {:?}", e)
+            }
+        });
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        on_message.forget(); // Lives as long as the page
+
+        Ok(RouteWorker { worker, next_id : Rc::new(Cell::new(0)) })
+    }
+
+    pub fn send(&self, request : &WorkerRequest) {
+        match serde_json::to_string(request) {
+            Ok(json) => if self.worker.post_message(&JsValue::from_str(&json)).is_err() {
+                warn!("Failed to send routing worker message");
+            },
+            Err(e) => warn!("Failed to serialise routing worker message: {:?}", e)
+        }
+    }
+
+    // The slot holds the id of the latest query of its kind, so older answers
+    // can be ignored. Without a query, the one in the slot is cancelled.
+    pub fn ask(&self, slot : StoredValue<Option<u64>>, query : Option<RouteQuery>) {
+        match query {
+            Some(query) => {
+                let id = self.next_id.get() + 1;
+                self.next_id.set(id);
+                slot.set_value(Some(id));
+                self.send(&WorkerRequest::Query { id, query : Box::new(query) });
+            },
+            None => if let Some(id) = slot.get_value() {
+                slot.set_value(None);
+                self.send(&WorkerRequest::Cancel(id));
+            }
+        }
+    }
+}
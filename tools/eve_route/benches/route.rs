@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use criterion::{criterion_group, criterion_main, Criterion};
+use petgraph::graph::{Graph, NodeIndex};
+use eve_sde::*;
+use eve_route::*;
+
+// The SDE shipped with the app, so the benchmark covers the whole of New Eden
+fn sde_systems() -> Vec<System> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../ref/sde.json");
+    let json = std::fs::read_to_string(path).expect("Failed to read sde.json");
+    Sde::from(serde_json::from_str::<SdeFile>(&json).expect("Failed to parse sde.json")).systems
+}
+
+fn sde_graph() -> (Graph<System, Connection>, HashMap<u32, NodeIndex>) {
+    gate_graph(&sde_systems())
+}
+
+fn system_id(graph : &Graph<System, Connection>, name : &str) -> u32 {
+    graph.node_weights().find(|s| s.name == name).unwrap_or_else(|| panic!("No system called {name}")).id
+}
+
+// A spread of wormholes like a busy Tripwire chain, every one between systems a fixed stride apart
+fn add_wormholes(graph : &mut Graph<System, Connection>, count : usize) {
+    let nodes = graph.node_indices().collect::<Vec<_>>();
+    let stride = nodes.len() / (count * 2);
+    for i in 0..count {
+        let (a, b) = (nodes[i * stride], nodes[nodes.len() - 1 - i * stride]);
+        let wormhole = WormholeAttributes {
+            signature : Some("ABC-123".to_owned()),
+            other_signature : Some("DEF-456".to_owned()),
+            wormhole_type : None,
+            side : WormholeSide::Unknown,
+            kind : WormholeKind::Unknown,
+            life : WormholeLife::Stable,
+            mass : WormholeMass::Stable,
+            jump_mass : None,
+            jump_mass_bound : None,
+            total_mass : None,
+            mass_regeneration : None,
+            expiry : None,
+            source : WormholeSource::Tripwire,
+            confidence : 1.0
+        };
+        graph.add_edge(a, b, Connection::Wormhole(wormhole.clone()));
+        graph.add_edge(b, a, Connection::Wormhole(wormhole));
+    }
+}
+
+// What each refresh costs: the gates are built once, then every change to the wormholes is overlaid on a copy
+fn graph_benchmark(c : &mut Criterion) {
+    let systems = sde_systems();
+    c.bench_function("gate graph", |b| b.iter(|| BaseGraph::new(&systems)));
+
+    let base = BaseGraph::new(&systems);
+    let mut with_wormholes = base.graph.clone();
+    add_wormholes(&mut with_wormholes, 50);
+    let edges = with_wormholes.edge_indices().skip(base.graph.edge_count()).map(|e| {
+        let (a, b) = with_wormholes.edge_endpoints(e).unwrap();
+        let Connection::Wormhole(wormhole) = &with_wormholes[e] else { unreachable!() };
+        OverlayEdge { from : with_wormholes[a].id, to : OverlayTarget::System(with_wormholes[b].id), wormhole : wormhole.clone() }
+    }).collect::<Vec<_>>();
+
+    c.bench_function("overlay 50 wormholes", |b| b.iter(|| base.overlay(&edges)));
+}
+
+fn route_benchmark(c : &mut Criterion) {
+    let (mut graph, _) = sde_graph();
+    add_wormholes(&mut graph, 50);
+
+    c.bench_function("landmarks", |b| b.iter(|| Landmarks::new(&graph, DEFAULT_LANDMARKS)));
+
+    let landmarks = Landmarks::new(&graph, DEFAULT_LANDMARKS);
+    let pairs = [("Jita", "Amarr"), ("Hek", "1DQ1-A"), ("Dodixie", "Tama"), ("Rens", "J100820")];
+
+    for (from, to) in pairs {
+        let request = RouteRequest { cost_model : CostModel::safest(), ..RouteRequest::new(system_id(&graph, from), system_id(&graph, to)) };
+        for k in [1, 5] {
+            c.bench_function(&format!("{from} to {to}, k = {k}, no heuristic"), |b| b.iter(|| find_routes(&graph, &request, k)));
+            c.bench_function(&format!("{from} to {to}, k = {k}, landmarks"), |b| b.iter(|| find_routes_with_landmarks(&graph, &landmarks, &request, k)));
+        }
+    }
+}
+
+criterion_group!(benches, graph_benchmark, route_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use eve_sde::SystemClass;
+
+use crate::expiry::ExpiryWindow;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WormholeLife {
+    #[serde(alias = "stable")]
+    Stable,
+
+    #[serde(alias = "critical")]
+    EOL,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WormholeMass {
+    #[serde(alias = "stable")]
+    Stable,
+
+    #[serde(alias = "destab")]
+    Destab,
+
+    #[serde(alias = "critical")]
+    VOC
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WormholeSource {
+    Tripwire,
+    EveScout,
+    Manual // Entered by hand, such as a hole heard about on comms
+}
+
+// Which end of the wormhole an edge enters through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WormholeSide {
+    Origin,  // The typed end, in the system the wormhole spawned in
+    K162,    // The exit end, opened once the origin was warped to or jumped
+    Unknown
+}
+
+impl WormholeSide {
+    pub fn reverse(&self) -> WormholeSide {
+        match self {
+            WormholeSide::Origin => WormholeSide::K162,
+            WormholeSide::K162 => WormholeSide::Origin,
+            WormholeSide::Unknown => WormholeSide::Unknown
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WormholeKind {
+    Static,    // Respawns in the origin system when it dies
+    Wandering,
+    Unknown    // Type or origin class not known
+}
+
+impl WormholeKind {
+    // static_in is None when the type isn't in the type database
+    pub fn classify(static_in : Option<&[SystemClass]>, origin_class : Option<&SystemClass>) -> WormholeKind {
+        match (static_in, origin_class) {
+            (Some(static_in), Some(origin_class)) if static_in.contains(origin_class) => WormholeKind::Static,
+            (Some(_), Some(_)) => WormholeKind::Wandering,
+            _ => WormholeKind::Unknown
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WormholeAttributes {
+    pub signature : Option<String>,
+    pub other_signature : Option<String>,
+    pub wormhole_type : Option<String>, // Type of the origin end, never K162
+    pub side : WormholeSide, // End we enter through
+    pub kind : WormholeKind,
+    pub life : WormholeLife,
+    pub mass : WormholeMass,
+    pub jump_mass : Option<u32>,
+    pub jump_mass_bound : Option<u32>, // Inferred from the classes at each end when the type isn't known
+    pub total_mass : Option<u32>,
+    pub mass_regeneration : Option<u32>, // Per day
+    pub expiry : Option<ExpiryWindow>,
+    pub source : WormholeSource,
+    pub confidence : f64 // From 0 to 1, how far the source's report of it is trusted
+}
+
+impl WormholeAttributes {
+    // The signature type seen on our end, None when we don't know which end we're on
+    pub fn entry(&self) -> Option<String> {
+        match self.side {
+            WormholeSide::Origin => Some(self.wormhole_type.clone().unwrap_or("????".to_owned())),
+            WormholeSide::K162 => Some("K162".to_owned()),
+            WormholeSide::Unknown => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JumpAttributes {
+    pub light_years : f64,
+    pub fuel : u32, // Isotopes
+    pub fatigue : f64, // Minutes of jump fatigue once through
+    pub reactivation : f64 // Minutes before the drive can jump again
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Connection {
+    Wormhole(WormholeAttributes),
+    Gate,
+    Jump(JumpAttributes) // Only in jump drive routes, never in the graph
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+
+// All costs are in jump-equivalents, added when entering the destination system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostModel {
+    pub jump : u32,
+    pub eol : u32,
+    pub voc : u32,
+    pub destab : u32,
+    pub lowsec : u32,
+    pub nullsec : u32,
+    pub eve_scout : u32,
+    pub unknown_jump_mass : u32,
+    pub low_confidence : u32 // In full for a wormhole with no confidence, scaled down as confidence rises
+}
+
+impl CostModel {
+    pub fn shortest() -> Self {
+        CostModel {
+            jump : 1,
+            eol : 0,
+            voc : 0,
+            destab : 0,
+            lowsec : 0,
+            nullsec : 0,
+            eve_scout : 0,
+            unknown_jump_mass : 0,
+            low_confidence : 0
+        }
+    }
+
+    pub fn safest() -> Self {
+        CostModel {
+            jump : 1,
+            eol : 5,
+            voc : 5,
+            destab : 2,
+            lowsec : 2,
+            nullsec : 3,
+            eve_scout : 1,
+            unknown_jump_mass : 1,
+            low_confidence : 4
+        }
+    }
+
+    pub fn cost(&self, system : &System, connection : &Connection) -> u32 {
+        let mut cost = self.jump;
+
+        cost += match system.class {
+            SystemClass::Lowsec => self.lowsec,
+            SystemClass::Nullsec => self.nullsec,
+            _ => 0
+        };
+
+        if let Connection::Wormhole(wormhole) = connection {
+            if wormhole.life == WormholeLife::EOL { cost += self.eol }
+            cost += match wormhole.mass {
+                WormholeMass::Stable => 0,
+                WormholeMass::Destab => self.destab,
+                WormholeMass::VOC => self.voc
+            };
+            if wormhole.source == WormholeSource::EveScout { cost += self.eve_scout }
+            if wormhole.jump_mass.is_none() { cost += self.unknown_jump_mass }
+            cost += (self.low_confidence as f64 * (1.0 - wormhole.confidence.clamp(0.0, 1.0))).round() as u32;
+        }
+
+        cost
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel::shortest()
+    }
+}
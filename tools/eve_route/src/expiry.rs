@@ -0,0 +1,62 @@
+use chrono::{NaiveDateTime, Duration};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::*;
+
+// An EOL wormhole has less than this long left
+pub const EOL_REMAINING_HOURS : i64 = 4;
+
+// Used when the wormhole type, and so its lifetime, is not known
+pub const DEFAULT_LIFETIME_HOURS : i64 = 24;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpiryWindow {
+    pub earliest : NaiveDateTime,
+    pub latest : NaiveDateTime
+}
+
+impl ExpiryWindow {
+    pub fn likely(&self) -> NaiveDateTime {
+        self.earliest + (self.latest - self.earliest) / 2
+    }
+}
+
+// Estimate from when the wormhole was first seen and its lifecycle flag as of now
+pub fn estimate_expiry(first_seen : NaiveDateTime, lifetime : Option<Duration>, life : &WormholeLife, now : NaiveDateTime) -> ExpiryWindow {
+    let type_expiry = first_seen + lifetime.unwrap_or(Duration::hours(DEFAULT_LIFETIME_HOURS));
+    let eol_boundary = now + Duration::hours(EOL_REMAINING_HOURS);
+
+    match life {
+        WormholeLife::EOL => ExpiryWindow {
+            earliest : now,
+            latest : type_expiry.clamp(now, eol_boundary)
+        },
+        WormholeLife::Stable => ExpiryWindow {
+            earliest : eol_boundary.min(type_expiry.max(now)),
+            latest : type_expiry.max(now)
+        }
+    }
+}
+
+// Estimate from a whole number of hours remaining, as reported at a given time
+pub fn remaining_hours_expiry(reported : NaiveDateTime, remaining_hours : u32) -> ExpiryWindow {
+    let earliest = reported + Duration::hours(remaining_hours as i64);
+    ExpiryWindow { earliest, latest : earliest + Duration::hours(1) }
+}
+
+// Full trust until a wormhole has gone distrust_after without an update, then
+// halving each time that long passes again
+pub fn update_confidence(updated : NaiveDateTime, now : NaiveDateTime, distrust_after : Option<Duration>) -> f64 {
+    let Some(distrust_after) = distrust_after.filter(|d| *d > Duration::zero()) else { return 1.0 };
+    let overdue = (now - updated) - distrust_after;
+    if overdue <= Duration::zero() { return 1.0 }
+    0.5f64.powf(overdue.num_seconds() as f64 / distrust_after.num_seconds() as f64)
+}
+
+// Whether a wormhole is likely still open at the given time
+pub fn likely_open(wormhole : &WormholeAttributes, at : NaiveDateTime) -> bool {
+    match &wormhole.expiry {
+        None => true,
+        Some(expiry) => at < expiry.likely()
+    }
+}
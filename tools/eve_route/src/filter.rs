@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use petgraph::graph::{Graph, NodeIndex, EdgeIndex};
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::mass::*;
+use crate::route::*;
+
+// One wormhole, picked out by its signatures, so others between the same systems are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CollapsedWormhole {
+    pub from_system : u32,
+    pub to_system : u32,
+    pub signature : Option<String>, // In from_system
+    pub other_signature : Option<String>
+}
+
+impl CollapsedWormhole {
+    pub fn new(from_system : u32, to_system : u32, wormhole : &WormholeAttributes) -> Self {
+        CollapsedWormhole { from_system, to_system, signature : wormhole.signature.clone(), other_signature : wormhole.other_signature.clone() }
+    }
+
+    // Either way through
+    pub fn matches(&self, from_system : u32, to_system : u32, wormhole : &WormholeAttributes) -> bool {
+        let forward = (self.from_system, self.to_system, &self.signature, &self.other_signature) == (from_system, to_system, &wormhole.signature, &wormhole.other_signature);
+        let reverse = (self.to_system, self.from_system, &self.other_signature, &self.signature) == (from_system, to_system, &wormhole.signature, &wormhole.other_signature);
+        forward || reverse
+    }
+}
+
+// The parts of a request that take systems and wormholes out of the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphFilters {
+    avoid_systems : Vec<u32>,
+    collapsed_wormholes : Vec<CollapsedWormhole>,
+    ship_size : u32,
+    unknown_jump_mass : UnknownJumpMass,
+    exclude_lowsec : bool,
+    exclude_nullsec : bool,
+    exclude_zarzakh : bool,
+    exclude_voc : bool,
+    exclude_eol : bool,
+    exclude_eve_scout : bool,
+    min_confidence : f64
+}
+
+impl From<&RouteRequest> for GraphFilters {
+    fn from(request : &RouteRequest) -> Self {
+        GraphFilters {
+            avoid_systems : request.avoid_systems.clone(),
+            collapsed_wormholes : request.collapsed_wormholes.clone(),
+            ship_size : request.ship_size,
+            unknown_jump_mass : request.unknown_jump_mass.clone(),
+            exclude_lowsec : request.exclude_lowsec,
+            exclude_nullsec : request.exclude_nullsec,
+            exclude_zarzakh : request.exclude_zarzakh,
+            exclude_voc : request.exclude_voc,
+            exclude_eol : request.exclude_eol,
+            exclude_eve_scout : request.exclude_eve_scout,
+            min_confidence : request.min_confidence
+        }
+    }
+}
+
+impl GraphFilters {
+    fn keeps_system(&self, system : &System) -> bool {
+        if self.avoid_systems.contains(&system.id) { return false }
+        if self.exclude_lowsec && system.class == SystemClass::Lowsec { return false }
+        if self.exclude_nullsec && system.class == SystemClass::Nullsec { return false }
+        if self.exclude_zarzakh && system.class == SystemClass::Zarzakh { return false }
+        true
+    }
+
+    fn keeps_wormhole(&self, from_system : u32, to_system : u32, wormhole : &WormholeAttributes) -> bool {
+        if self.collapsed_wormholes.iter().any(|c| c.matches(from_system, to_system, wormhole)) { return false }
+        if self.exclude_voc && wormhole.mass == WormholeMass::VOC { return false }
+        if self.exclude_eol && wormhole.life == WormholeLife::EOL { return false }
+        if self.exclude_eve_scout && wormhole.source == WormholeSource::EveScout { return false }
+        if wormhole.confidence < self.min_confidence { return false }
+        match (wormhole.jump_mass, wormhole.jump_mass_bound) {
+            (Some(jump_mass), _) => self.ship_size <= jump_mass,
+            (None, bound) => {
+                if bound.is_some_and(|b| self.ship_size > b) { return false }
+                let fits_anything = self.ship_size <= SMALLEST_JUMP_MASS;
+                self.unknown_jump_mass != UnknownJumpMass::Impassable || fits_anything
+            }
+        }
+    }
+}
+
+// A graph with a request's filters applied, and an id index into it. Searches
+// with the same filters can share one, so it's only rebuilt when they change.
+#[derive(Debug, Clone)]
+pub struct FilteredGraph {
+    pub graph : Graph<System, Connection>,
+    pub node_index : HashMap<u32, NodeIndex>,
+    pub filters : GraphFilters
+}
+
+impl FilteredGraph {
+    pub fn new(graph : &Graph<System, Connection>, request : &RouteRequest) -> Self {
+        let filters = GraphFilters::from(request);
+        let filtered = graph.filter_map(
+            |_, system| filters.keeps_system(system).then(|| system.clone()),
+            |e, connection| match connection {
+                Connection::Wormhole(wormhole) => {
+                    let (from_system, to_system) = endpoint_ids(graph, e)?;
+                    filters.keeps_wormhole(from_system, to_system, wormhole).then(|| connection.clone())
+                },
+                Connection::Gate | Connection::Jump(_) => Some(connection.clone())
+            }
+        );
+        let node_index = filtered.node_indices().map(|n| (filtered[n].id, n)).collect();
+        FilteredGraph { graph : filtered, node_index, filters }
+    }
+
+    // Whether the request filters the graph the same way
+    pub fn matches(&self, request : &RouteRequest) -> bool {
+        self.filters == GraphFilters::from(request)
+    }
+
+    // None when the system was filtered out or isn't in the graph
+    pub fn node(&self, system_id : u32) -> Option<NodeIndex> {
+        self.node_index.get(&system_id).copied()
+    }
+}
+
+fn endpoint_ids(graph : &Graph<System, Connection>, e : EdgeIndex) -> Option<(u32, u32)> {
+    let (source, target) = graph.edge_endpoints(e)?;
+    Some((graph[source].id, graph[target].id))
+}
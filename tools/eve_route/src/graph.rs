@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use petgraph::graph::{Graph, NodeIndex};
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::lead::*;
+
+// The static gate graph, before any wormholes are added
+pub fn gate_graph(systems : &[System]) -> (Graph<System, Connection>, HashMap<u32, NodeIndex>) {
+    let mut graph = Graph::<System, Connection>::new();
+    let mut node_index = HashMap::<u32, NodeIndex>::new();
+
+    for system in systems {
+        node_index.insert(system.id, graph.add_node(system.clone()));
+    }
+
+    for system in systems {
+        let index1 = node_index[&system.id]; // Can't be missing, we just added it
+        for neighbour in &system.neighbours {
+            let Some(index2) = node_index.get(neighbour) else { continue };
+            graph.add_edge(index1, *index2, Connection::Gate);
+            graph.add_edge(*index2, index1, Connection::Gate);
+        }
+    }
+
+    (graph, node_index)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OverlayTarget {
+    System(u32),
+    Lead(SystemClass) // Unscouted, so only the class is known
+}
+
+// A directed wormhole edge to lay over the gate graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverlayEdge {
+    pub from : u32,
+    pub to : OverlayTarget,
+    pub wormhole : WormholeAttributes
+}
+
+// The gate graph and its id index, built once per SDE. Wormholes change far
+// more often, so they're overlaid on a copy rather than rebuilding the gates.
+#[derive(Debug, Clone)]
+pub struct BaseGraph {
+    pub graph : Graph<System, Connection>,
+    pub node_index : HashMap<u32, NodeIndex>
+}
+
+impl BaseGraph {
+    pub fn new(systems : &[System]) -> Self {
+        let (graph, node_index) = gate_graph(systems);
+        BaseGraph { graph, node_index }
+    }
+
+    pub fn system(&self, system_id : u32) -> Option<&System> {
+        self.node_index.get(&system_id).map(|n| &self.graph[*n])
+    }
+
+    // Node indices of the gate graph are kept, so the index still applies. Edges
+    // from or to systems not in the SDE are skipped.
+    pub fn overlay(&self, edges : &[OverlayEdge]) -> Graph<System, Connection> {
+        let mut graph = self.graph.clone();
+        let mut node_index = self.node_index.clone();
+
+        for edge in edges {
+            let Some(from) = node_index.get(&edge.from).copied() else { continue };
+            match &edge.to {
+                OverlayTarget::System(to) => {
+                    let Some(to) = node_index.get(to).copied() else { continue };
+                    graph.add_edge(from, to, Connection::Wormhole(edge.wormhole.clone()));
+                },
+                OverlayTarget::Lead(class) => add_lead(&mut graph, &mut node_index, from, class, edge.wormhole.clone())
+            }
+        }
+
+        graph
+    }
+}
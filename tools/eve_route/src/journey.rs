@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+use petgraph::algo;
+use petgraph::graph::NodeIndex;
+
+use crate::filter::*;
+use crate::landmarks::*;
+use crate::route::*;
+
+pub const MAX_OPTIMISED_VIA_SYSTEMS : usize = 10;
+
+pub(crate) fn find_journey(filtered : &FilteredGraph, landmarks : Option<&Landmarks>, from_system_node : NodeIndex, to_system_node : NodeIndex, request : &RouteRequest) -> Result<RouteResult, RouteError> {
+    let filtered_graph = &filtered.graph;
+    let edge_cost = edge_cost(filtered_graph, request);
+
+    let mut via_system_nodes = request.via_systems.iter()
+        .map(|v| filtered.node(*v).ok_or(RouteError::ViaSystemFiltered))
+        .collect::<Result<Vec<_>,_>>()?;
+
+    if request.optimise_via_order {
+        if via_system_nodes.len() > MAX_OPTIMISED_VIA_SYSTEMS { return Err(RouteError::TooManyViaSystems) }
+
+        let stops = [vec![from_system_node], via_system_nodes.clone(), vec![to_system_node]].concat();
+
+        // The last stop is never a source, so skip its search
+        let costs = stops[..stops.len() - 1].iter().map(|source| {
+            let reachable = algo::dijkstra(filtered_graph, *source, None, edge_cost);
+            stops.iter().map(|target| reachable.get(target).copied()).collect::<Vec<_>>()
+        }).collect::<Vec<_>>();
+
+        via_system_nodes = optimise_order(&costs).ok_or(RouteError::NoPath)?
+            .into_iter()
+            .map(|v| via_system_nodes[v])
+            .collect();
+    }
+
+    let stops = [vec![from_system_node], via_system_nodes, vec![to_system_node]].concat();
+
+    let mut hops = Vec::<RouteHop>::new();
+    let mut departure = request.departure;
+    for (leg, (n1, n2)) in stops.iter().zip(stops.iter().skip(1)).enumerate() {
+        // Each leg departs later, so check expiry again from where it starts
+        let leg_graph = match (departure, request.exclude_expiring) {
+            (Some(departure), true) => {
+                let mut leg_graph = filtered_graph.clone();
+                filter_expiring(&mut leg_graph, *n1, departure, &request.travel_time);
+                Cow::Owned(leg_graph)
+            },
+            _ => Cow::Borrowed(filtered_graph)
+        };
+
+        let bounds = LowerBounds::new(&leg_graph, landmarks, request, *n2);
+
+        let (_, path) = algo::astar(
+            leg_graph.as_ref(),
+            *n1,
+            |n| n == *n2,
+            edge_cost,
+            |n| bounds.bound(n),
+        ).ok_or(RouteError::NoPath)?;
+
+        let leg_hops = hops_from_path(&leg_graph, path, request, leg, departure)?;
+        if let Some(hop) = leg_hops.last() { departure = hop.eta }
+        hops.extend(leg_hops);
+    }
+
+    Ok(RouteResult { hops, stops : stops.into_iter().map(|n| filtered_graph[n].clone()).collect() })
+}
+
+// Held-Karp over the via systems, with the first and last stops fixed. Row
+// 0 of costs is the first stop, the last column is the last stop, and the
+// result indexes the via systems in visiting order.
+fn optimise_order(costs : &[Vec<Option<u32>>]) -> Option<Vec<usize>> {
+    let via_count = costs.len() - 1;
+    let last = via_count + 1;
+    let full = (1usize << via_count) - 1;
+
+    if via_count == 0 { return Some(vec![]) }
+
+    // best[mask][j] is the cheapest (cost, previous via) visiting mask and finishing at via j
+    let mut best = vec![vec![Option::<(u32, Option<usize>)>::None; via_count]; full + 1];
+
+    for j in 0..via_count {
+        best[1 << j][j] = costs[0][j + 1].map(|c| (c, None));
+    }
+
+    for mask in 1..=full {
+        for j in 0..via_count {
+            let Some((cost, _)) = best[mask][j] else { continue };
+            for next in 0..via_count {
+                if mask & (1 << next) != 0 { continue }
+                let Some(step) = costs[j + 1][next + 1] else { continue };
+                let entry = &mut best[mask | (1 << next)][next];
+                if !matches!(entry, Some((c, _)) if *c <= cost + step) {
+                    *entry = Some((cost + step, Some(j)));
+                }
+            }
+        }
+    }
+
+    let (mut current, _) = (0..via_count)
+        .filter_map(|j| Some((j, best[full][j]?.0 + costs[j + 1][last]?)))
+        .min_by_key(|(_, cost)| *cost)?;
+
+    let mut order = vec![current];
+    let mut mask = full;
+    while let Some((_, Some(previous))) = best[mask][current] {
+        mask &= !(1 << current);
+        current = previous;
+        order.push(current);
+    }
+
+    order.reverse();
+    Some(order)
+}
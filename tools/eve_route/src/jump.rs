@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use chrono::Duration;
+use petgraph::graph::{Graph, NodeIndex};
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::filter::*;
+use crate::route::*;
+
+pub const METRES_PER_LIGHT_YEAR : f64 = 9_460_730_472_580_800.0;
+
+// Jump fatigue rules, in minutes
+const MIN_FATIGUE : f64 = 10.0;
+const MAX_FATIGUE : f64 = 300.0;
+const MAX_REACTIVATION : f64 = 30.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JumpObjective {
+    Jumps,  // Fewest jumps, then least fatigue
+    Fatigue // Least fatigue on arrival, then fewest jumps
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JumpDrive {
+    pub range : f64, // Light years
+    pub fuel_per_light_year : f64, // Isotopes, after skills
+    pub fatigue_factor : f64, // Fraction of each light year counted for fatigue, 0.1 for jump freighters and black ops
+    pub objective : JumpObjective
+}
+
+impl JumpDrive {
+    // A jump starting with the fatigue left once the drive is ready again
+    pub fn jump(&self, fatigue : f64, light_years : f64) -> JumpAttributes {
+        let effective = light_years * self.fatigue_factor;
+        let fatigue = (fatigue.max(MIN_FATIGUE) * (1.0 + effective)).min(MAX_FATIGUE);
+        JumpAttributes {
+            light_years,
+            fuel : (light_years * self.fuel_per_light_year).ceil() as u32,
+            fatigue,
+            reactivation : (1.0 + effective).max(fatigue / 10.0).min(MAX_REACTIVATION)
+        }
+    }
+}
+
+// Fatigue decays while waiting for the drive to reactivate
+fn ready_fatigue(jump : &JumpAttributes) -> f64 {
+    (jump.fatigue - jump.reactivation).max(0.0)
+}
+
+// Jump drives cannot be lit in or jumped to highsec, wormhole space, Pochven or Zarzakh
+pub fn jump_allowed(system : &System) -> bool {
+    matches!(system.class, SystemClass::Lowsec | SystemClass::Nullsec)
+}
+
+pub fn light_years(a : &Position, b : &Position) -> f64 {
+    a.distance(b) / METRES_PER_LIGHT_YEAR
+}
+
+// Systems bucketed into cubes one jump range across, so only the neighbouring
+// 27 cubes need checking for systems in range
+struct SpatialIndex<'a> {
+    graph : &'a Graph<System, Connection>,
+    range : f64, // Light years
+    cells : HashMap<(i64, i64, i64), Vec<(NodeIndex, Position)>>
+}
+
+impl<'a> SpatialIndex<'a> {
+    fn new(graph : &'a Graph<System, Connection>, range : f64) -> Self {
+        let mut index = SpatialIndex { graph, range, cells : HashMap::new() };
+        for n in graph.node_indices() {
+            let Some(position) = graph[n].position.filter(|_| jump_allowed(&graph[n])) else { continue };
+            index.cells.entry(index.cell(&position)).or_default().push((n, position));
+        }
+        index
+    }
+
+    fn cell(&self, position : &Position) -> (i64, i64, i64) {
+        let size = self.range.max(f64::MIN_POSITIVE) * METRES_PER_LIGHT_YEAR;
+        ((position.x / size).floor() as i64, (position.y / size).floor() as i64, (position.z / size).floor() as i64)
+    }
+
+    fn in_range(&self, from : NodeIndex) -> impl Iterator<Item = (NodeIndex, f64)> + '_ {
+        let position = self.graph[from].position.unwrap_or(Position { x : 0.0, y : 0.0, z : 0.0 });
+        let (x, y, z) = self.cell(&position);
+        (-1..=1).flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (x + dx, y + dy, z + dz))))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |(n, _)| *n != from)
+            .map(move |(n, p)| (*n, light_years(&position, p)))
+            .filter(|(_, light_years)| *light_years <= self.range)
+    }
+}
+
+// Whole seconds, so labels have a total order
+fn seconds(minutes : f64) -> u64 {
+    (minutes * 60.0).round() as u64
+}
+
+// One way to reach a system, in a search layer
+struct JumpLabel {
+    node : NodeIndex,
+    fatigue : f64, // Once the drive is ready again
+    previous : Option<(usize, JumpAttributes)> // Label jumped from
+}
+
+// Fewest jumps or least fatigue from one system to another, starting with some
+// fatigue already. Searched a jump at a time, as fatigue can fall from one jump
+// to the next once the reactivation wait decays it, such as at the cap. Later
+// jumps only depend on the fatigue left, so a way to a system is only kept if
+// it leaves less fatigue than every way there with as few jumps. That is exact,
+// except that routes never pass a system twice.
+fn jump_path(index : &SpatialIndex, drive : &JumpDrive, from : NodeIndex, to : NodeIndex, fatigue : f64) -> Option<Vec<(NodeIndex, JumpAttributes)>> {
+    let key = |jumps : usize, fatigue : f64| match drive.objective {
+        JumpObjective::Jumps => (jumps as u64, seconds(fatigue)),
+        JumpObjective::Fatigue => (seconds(fatigue), jumps as u64)
+    };
+
+    if from == to { return Some(vec![]) }
+
+    let mut labels = vec![JumpLabel { node : from, fatigue, previous : None }];
+    let mut least_fatigue = HashMap::from([(from, seconds(fatigue))]);
+    let mut arrival = None::<((u64, u64), usize, JumpAttributes)>;
+    let mut layer = 0..1;
+    let mut jumps = 0;
+
+    let on_path = |labels : &[JumpLabel], node : NodeIndex, mut label : usize| loop {
+        if labels[label].node == node { return true }
+        match &labels[label].previous {
+            Some((previous, _)) => label = *previous,
+            None => return false
+        }
+    };
+
+    while !layer.is_empty() {
+        // Every later arrival has more jumps
+        if arrival.is_some() && drive.objective == JumpObjective::Jumps { break }
+        jumps += 1;
+
+        let start = labels.len();
+        for label in layer {
+            for (next, light_years) in index.in_range(labels[label].node) {
+                let jump = drive.jump(labels[label].fatigue, light_years);
+                if next == to {
+                    let next_key = key(jumps, jump.fatigue);
+                    if arrival.as_ref().is_none_or(|(k, _, _)| next_key < *k) { arrival = Some((next_key, label, jump)) }
+                    continue
+                }
+                let fatigue = ready_fatigue(&jump);
+                if least_fatigue.get(&next).is_some_and(|f| *f <= seconds(fatigue)) || on_path(&labels, next, label) { continue }
+                least_fatigue.insert(next, seconds(fatigue));
+                labels.push(JumpLabel { node : next, fatigue, previous : Some((label, jump)) });
+            }
+        }
+        layer = start..labels.len();
+    }
+
+    let (_, mut label, jump) = arrival?;
+    let mut path = vec![(to, jump)];
+    while let Some((previous, jump)) = &labels[label].previous {
+        path.push((labels[label].node, jump.clone()));
+        label = *previous;
+    }
+    path.reverse();
+    Some(path)
+}
+
+// Jump drive routes visit the via systems in order and carry fatigue between legs
+pub(crate) fn find_jump_route(filtered : &FilteredGraph, request : &RouteRequest, drive : &JumpDrive) -> Result<RouteResult, RouteError> {
+    let filtered_graph = &filtered.graph;
+
+    let from_system_node = filtered.node(request.from_system).ok_or(RouteError::FromSystemFiltered)?;
+    let to_system_node = filtered.node(request.to_system).ok_or(RouteError::ToSystemFiltered)?;
+    let via_system_nodes = request.via_systems.iter()
+        .map(|v| filtered.node(*v).ok_or(RouteError::ViaSystemFiltered))
+        .collect::<Result<Vec<_>,_>>()?;
+
+    let stops = [vec![from_system_node], via_system_nodes, vec![to_system_node]].concat();
+
+    for stop in &stops {
+        if !jump_allowed(&filtered_graph[*stop]) { return Err(RouteError::JumpNotAllowed) }
+        if filtered_graph[*stop].position.is_none() { return Err(RouteError::NoPositions) }
+    }
+
+    let index = SpatialIndex::new(filtered_graph, drive.range);
+
+    let mut hops = Vec::<RouteHop>::new();
+    let mut fatigue = 0.0;
+    let mut time = request.departure;
+    for (leg, (n1, n2)) in stops.iter().zip(stops.iter().skip(1)).enumerate() {
+        for (node, jump) in jump_path(&index, drive, *n1, *n2, fatigue).ok_or(RouteError::NoPath)? {
+            fatigue = ready_fatigue(&jump);
+            let system = filtered_graph[node].clone();
+            let eta = time;
+            time = time.map(|t| t + Duration::seconds(seconds(jump.reactivation) as i64));
+            let connection = Connection::Jump(jump);
+            let cost = request.cost(&system, &connection);
+            hops.push(RouteHop { system, connection, cost, mass_verdict : None, leg, eta, fallbacks : vec![] });
+        }
+    }
+
+    Ok(RouteResult { hops, stops : stops.into_iter().map(|n| filtered_graph[n].clone()).collect() })
+}
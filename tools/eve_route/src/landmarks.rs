@@ -0,0 +1,149 @@
+use std::collections::{HashMap, VecDeque};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::Direction;
+use petgraph::visit::EdgeRef;
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::route::*;
+
+pub const DEFAULT_LANDMARKS : usize = 16;
+
+const UNREACHED : u32 = u32::MAX;
+
+// Heuristic for systems that cannot reach the target at all. Any value is
+// admissible there, this one just leaves room for A* to add costs to it.
+const UNREACHABLE_BOUND : u32 = u32::MAX / 2;
+
+// Gate jump counts from a few well spread landmark systems, for ALT lower
+// bounds (A*, landmarks, triangle inequality). Only gates are used because
+// they never change, wormholes are bounded separately on each search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Landmarks {
+    index : HashMap<u32, usize>, // System id to its row of distances
+    count : usize,
+    distances : Vec<u32> // Row per system, column per landmark, UNREACHED when there is no gate path
+}
+
+impl Landmarks {
+    pub fn new(graph : &Graph<System, Connection>, count : usize) -> Self {
+        let index = graph.node_indices().map(|n| (graph[n].id, n.index())).collect::<HashMap<_,_>>();
+        let gated = graph.node_indices()
+            .filter(|n| graph.edges(*n).any(|e| *e.weight() == Connection::Gate))
+            .collect::<Vec<_>>();
+
+        // Farthest point selection, where a system no landmark reaches is farthest of all
+        let mut columns = Vec::<Vec<u32>>::new();
+        let mut nearest = vec![UNREACHED; graph.node_count()];
+        let mut next = gated.first().copied();
+        while let Some(landmark) = next.filter(|_| columns.len() < count) {
+            let column = gate_jumps(graph, &[landmark]);
+            for (n, d) in nearest.iter_mut().zip(&column) { *n = (*n).min(*d) }
+            columns.push(column);
+            next = gated.iter().filter(|n| nearest[n.index()] > 0).max_by_key(|n| nearest[n.index()]).copied();
+        }
+
+        let count = columns.len();
+        let distances = (0..graph.node_count()).flat_map(|n| columns.iter().map(move |c| c[n])).collect();
+        Landmarks { index, count, distances }
+    }
+
+    fn row(&self, system_id : u32) -> Option<&[u32]> {
+        let row = *self.index.get(&system_id)?;
+        Some(&self.distances[row * self.count..(row + 1) * self.count])
+    }
+
+    // Lower bound on the gate jumps between two systems, None if no gates connect them
+    pub fn gate_lower_bound(&self, from_system : u32, to_system : u32) -> Option<u32> {
+        let (Some(from), Some(to)) = (self.row(from_system), self.row(to_system)) else { return Some(0) };
+        row_lower_bound(from, to)
+    }
+}
+
+fn row_lower_bound(from : &[u32], to : &[u32]) -> Option<u32> {
+    let mut bound = 0;
+    for (a, b) in from.iter().zip(to) {
+        match (*a, *b) {
+            (UNREACHED, UNREACHED) => (),
+            (UNREACHED, _) | (_, UNREACHED) => return None,
+            (a, b) => bound = bound.max(a.abs_diff(b))
+        }
+    }
+    Some(bound)
+}
+
+// Multi-source breadth first gate jump counts, indexed by node
+fn gate_jumps(graph : &Graph<System, Connection>, sources : &[NodeIndex]) -> Vec<u32> {
+    let mut jumps = vec![UNREACHED; graph.node_count()];
+    let mut queue = VecDeque::new();
+    for source in sources {
+        jumps[source.index()] = 0;
+        queue.push_back(*source);
+    }
+    while let Some(node) = queue.pop_front() {
+        let next = jumps[node.index()] + 1;
+        for edge in graph.edges(node) {
+            if *edge.weight() != Connection::Gate || jumps[edge.target().index()] != UNREACHED { continue }
+            jumps[edge.target().index()] = next;
+            queue.push_back(edge.target());
+        }
+    }
+    jumps
+}
+
+// Admissible A* heuristic towards a target, worked out as nodes are reached.
+// A path either uses gates only, bounded by the landmarks, or leaves by gates
+// to some wormhole, takes at least one wormhole, and arrives by gates from
+// another. Every hop costs at least the cost model's jump cost.
+pub(crate) struct LowerBounds<'a> {
+    graph : &'a Graph<System, Connection>,
+    landmarks : Option<&'a Landmarks>,
+    target : NodeIndex,
+    target_row : Option<&'a [u32]>,
+    to_wormhole : Vec<u32>, // Gate jumps to the nearest system with a wormhole in or out
+    jump_cost : u32
+}
+
+impl<'a> LowerBounds<'a> {
+    pub(crate) fn new(graph : &'a Graph<System, Connection>, landmarks : Option<&'a Landmarks>, request : &RouteRequest, target : NodeIndex) -> Self {
+        let to_wormhole = match landmarks {
+            None => vec![],
+            Some(_) => {
+                // Either way, as expiring wormholes may only be taken out in one direction
+                let wormhole_systems = graph.node_indices()
+                    .filter(|n| graph.edges_directed(*n, Direction::Outgoing).chain(graph.edges_directed(*n, Direction::Incoming))
+                        .any(|e| matches!(e.weight(), Connection::Wormhole(_))))
+                    .collect::<Vec<_>>();
+                gate_jumps(graph, &wormhole_systems)
+            }
+        };
+
+        LowerBounds {
+            graph,
+            landmarks,
+            target,
+            target_row : landmarks.and_then(|l| l.row(graph[target].id)),
+            to_wormhole,
+            jump_cost : request.cost_model.jump
+        }
+    }
+
+    pub(crate) fn bound(&self, n : NodeIndex) -> u32 {
+        let Some(landmarks) = self.landmarks else { return 0 };
+        if n == self.target { return 0 }
+
+        let by_gates = match (landmarks.row(self.graph[n].id), self.target_row) {
+            (Some(row), Some(target_row)) => row_lower_bound(row, target_row),
+            _ => Some(0)
+        };
+        let by_wormholes = match (self.to_wormhole[n.index()], self.to_wormhole[self.target.index()]) {
+            (UNREACHED, _) | (_, UNREACHED) => None,
+            (a, b) => Some(a + 1 + b)
+        };
+
+        match by_gates.into_iter().chain(by_wormholes).min() {
+            Some(jumps) => jumps.saturating_mul(self.jump_cost).min(UNREACHABLE_BOUND),
+            None => UNREACHABLE_BOUND
+        }
+    }
+}
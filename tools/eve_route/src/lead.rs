@@ -0,0 +1,149 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use petgraph::algo;
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::{EdgeRef, Reversed};
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::filter::*;
+use crate::route::*;
+
+// Virtual systems stand in for the unscouted far side of a lead, one per class.
+// Real system ids are all below this.
+pub const LEAD_SYSTEM_ID_BASE : u32 = 40_000_000;
+
+pub fn lead_system_id(class : &SystemClass) -> u32 {
+    LEAD_SYSTEM_ID_BASE + class.clone() as u32
+}
+
+pub fn is_lead_system(system_id : u32) -> bool {
+    system_id >= LEAD_SYSTEM_ID_BASE
+}
+
+pub fn lead_system(class : &SystemClass) -> System {
+    let security = match class {
+        SystemClass::Highsec => 5,
+        SystemClass::Lowsec => 1,
+        _ => -5
+    };
+    System::from(SystemV1 {
+        id : lead_system_id(class),
+        name : format!("Unscouted {:?}", class),
+        security,
+        class : class.clone(),
+        neighbours : vec![]
+    })
+}
+
+// A wormhole whose far side is only known by class. Lead systems only ever
+// get edges in, so no route passes through one, it can only end there.
+pub fn add_lead(graph : &mut Graph<System, Connection>, node_index : &mut HashMap<u32, NodeIndex>, from : NodeIndex, class : &SystemClass, wormhole : WormholeAttributes) {
+    let lead_index = *node_index.entry(lead_system_id(class)).or_insert_with(|| graph.add_node(lead_system(class)));
+    graph.add_edge(from, lead_index, Connection::Wormhole(wormhole));
+}
+
+// The cheapest way to any system of a class, known or through a lead. The
+// request's to_system and via_systems are not used.
+pub fn find_exploratory_route(graph : &Graph<System, Connection>, request : &RouteRequest, class : &SystemClass) -> Result<RouteResult, RouteError> {
+    find_exploratory_route_filtered(&FilteredGraph::new(graph, request), request, class)
+}
+
+// The graph must already have the request's filters applied
+pub fn find_exploratory_route_filtered(filtered : &FilteredGraph, request : &RouteRequest, class : &SystemClass) -> Result<RouteResult, RouteError> {
+    let from_system_node = filtered.node(request.from_system).ok_or(RouteError::FromSystemFiltered)?;
+
+    let filtered_graph = match (request.departure, request.exclude_expiring) {
+        (Some(departure), true) => {
+            let mut graph = filtered.graph.clone();
+            filter_expiring(&mut graph, from_system_node, departure, &request.travel_time);
+            Cow::Owned(graph)
+        },
+        _ => Cow::Borrowed(&filtered.graph)
+    };
+    let filtered_graph = filtered_graph.as_ref();
+
+    let (_, path) = algo::astar(
+        filtered_graph,
+        from_system_node,
+        |n| n != from_system_node && filtered_graph[n].class == *class,
+        edge_cost(filtered_graph, request),
+        |_| 0
+    ).ok_or(RouteError::NoPath)?;
+
+    let stops = vec![filtered_graph[from_system_node].clone(), filtered_graph[*path.last().unwrap()].clone()];
+    Ok(RouteResult { hops : hops_from_path(filtered_graph, path, request, 0, request.departure)?, stops })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lead {
+    pub system : System, // Where the signature is
+    pub class : SystemClass, // Of the unscouted side
+    pub wormhole : WormholeAttributes,
+    pub chance : f64, // Fraction of systems in the class that would give a cheaper route, or any route if there is none now
+    pub saving : f64 // Expected cost saved on the route, zero when there is no route now
+}
+
+// Ranks leads by how much scouting each would be expected to shorten the
+// route from the request's from_system to its to_system, assuming the far
+// side is equally likely to be any system of its class
+pub fn rank_leads(graph : &Graph<System, Connection>, request : &RouteRequest) -> Result<Vec<Lead>, RouteError> {
+    rank_leads_filtered(&FilteredGraph::new(graph, request), request)
+}
+
+// The graph must already have the request's filters applied
+pub fn rank_leads_filtered(filtered : &FilteredGraph, request : &RouteRequest) -> Result<Vec<Lead>, RouteError> {
+    let filtered_graph = &filtered.graph;
+
+    let from_system_node = filtered.node(request.from_system).ok_or(RouteError::FromSystemFiltered)?;
+    let to_system_node = filtered.node(request.to_system).ok_or(RouteError::ToSystemFiltered)?;
+
+    let from_costs = algo::dijkstra(filtered_graph, from_system_node, None, edge_cost(filtered_graph, request));
+
+    // Reversed edges start where the original entered, which is what the cost depends on
+    let to_costs = algo::dijkstra(Reversed(filtered_graph), to_system_node, None, |e| {
+        request.cost(&filtered_graph[e.source()], e.weight())
+    });
+
+    let baseline = from_costs.get(&to_system_node).copied();
+
+    let mut class_systems = HashMap::<u32, Vec<NodeIndex>>::new();
+    for n in filtered_graph.node_indices().filter(|n| !is_lead_system(filtered_graph[*n].id)) {
+        class_systems.entry(filtered_graph[n].class.clone() as u32).or_default().push(n);
+    }
+
+    let mut leads = filtered_graph.edge_references()
+        .filter(|e| is_lead_system(filtered_graph[e.target()].id))
+        .filter_map(|e| {
+            let Connection::Wormhole(wormhole) = e.weight() else { return None };
+            let to_lead = from_costs.get(&e.source())? + request.cost(&filtered_graph[e.target()], e.weight());
+            let class = filtered_graph[e.target()].class.clone();
+            let exits = class_systems.get(&(class.clone() as u32)).map(|v| v.as_slice()).unwrap_or_default();
+
+            let savings = exits.iter()
+                .filter_map(|n| to_costs.get(n).map(|c| to_lead + c))
+                .map(|via| match baseline {
+                    Some(baseline) => baseline.saturating_sub(via),
+                    None => 1
+                })
+                .filter(|saving| *saving > 0)
+                .collect::<Vec<_>>();
+
+            let count = exits.len().max(1) as f64;
+            Some(Lead {
+                system : filtered_graph[e.source()].clone(),
+                class,
+                wormhole : wormhole.clone(),
+                chance : savings.len() as f64 / count,
+                saving : match baseline {
+                    Some(_) => savings.iter().sum::<u32>() as f64 / count,
+                    None => 0.0
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    leads.sort_by(|a, b| b.saving.total_cmp(&a.saving).then(b.chance.total_cmp(&a.chance)));
+    Ok(leads)
+}
@@ -0,0 +1,27 @@
+mod connection;
+mod cost;
+mod expiry;
+mod filter;
+mod graph;
+mod journey;
+mod jump;
+mod landmarks;
+mod lead;
+mod mass;
+mod route;
+mod service;
+mod travel;
+
+pub use connection::*;
+pub use cost::*;
+pub use expiry::*;
+pub use filter::*;
+pub use graph::*;
+pub use journey::MAX_OPTIMISED_VIA_SYSTEMS;
+pub use jump::{JumpDrive, JumpObjective, METRES_PER_LIGHT_YEAR, jump_allowed, light_years};
+pub use landmarks::{Landmarks, DEFAULT_LANDMARKS};
+pub use lead::*;
+pub use mass::*;
+pub use route::*;
+pub use service::*;
+pub use travel::{TravelTime, DEFAULT_WARP_SPEED, METRES_PER_AU, system_warps, warp_duration};
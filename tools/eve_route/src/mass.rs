@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+
+// Masses are in millions of kg, the same unit as WormholeAttributes::jump_mass
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fleet {
+    pub ship_mass : f64,
+    pub ship_count : u32,
+    pub round_trip : bool,
+    pub hours_before_return : f64
+}
+
+impl Fleet {
+    pub fn mass(&self) -> f64 {
+        self.ship_mass * self.ship_count as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MassVerdict {
+    Safe,     // Fits even in the worst case
+    Risky,    // Might collapse the wormhole
+    Collapse, // Will collapse the wormhole before the whole fleet is through
+    TooHeavy, // A single ship exceeds the jump mass
+    Unknown   // Wormhole type not known
+}
+
+// Wormhole total mass varies by up to 10% either side of the nominal value
+const TOTAL_MASS_VARIANCE : f64 = 0.1;
+
+// Remaining fraction of total mass implied by each visible mass state
+fn remaining_fraction(mass : &WormholeMass) -> (f64, f64) {
+    match mass {
+        WormholeMass::Stable => (0.5, 1.0),
+        WormholeMass::Destab => (0.1, 0.5),
+        WormholeMass::VOC => (0.0, 0.1)
+    }
+}
+
+pub fn remaining_mass(wormhole : &WormholeAttributes) -> Option<(f64, f64)> {
+    let total_mass = wormhole.total_mass? as f64;
+    let (low, high) = remaining_fraction(&wormhole.mass);
+    Some((
+        total_mass * (1.0 - TOTAL_MASS_VARIANCE) * low,
+        total_mass * (1.0 + TOTAL_MASS_VARIANCE) * high
+    ))
+}
+
+pub fn mass_verdict(wormhole : &WormholeAttributes, fleet : &Fleet) -> MassVerdict {
+    if let Some(jump_mass) = wormhole.jump_mass.or(wormhole.jump_mass_bound) {
+        if fleet.ship_mass > jump_mass as f64 { return MassVerdict::TooHeavy }
+    }
+
+    let Some((low, high)) = remaining_mass(wormhole) else { return MassVerdict::Unknown };
+
+    let required = match fleet.round_trip {
+        false => fleet.mass(),
+        true => {
+            // Regeneration can only give back what the outbound trip used
+            let regenerated = wormhole.mass_regeneration.unwrap_or(0) as f64 * fleet.hours_before_return / 24.0;
+            fleet.mass() + (fleet.mass() - regenerated).max(0.0)
+        }
+    };
+
+    if required <= low { MassVerdict::Safe }
+    else if required <= high { MassVerdict::Risky }
+    else { MassVerdict::Collapse }
+}
+
+// Smallest jump mass of any wormhole, so ships up to this fit through every hole
+pub const SMALLEST_JUMP_MASS : u32 = 5;
+
+// Game rules for every wormhole touching a class, whatever its type
+fn class_limit(class : &SystemClass) -> Option<u32> {
+    match class {
+        SystemClass::C13 => Some(SMALLEST_JUMP_MASS), // Frigate holes only
+        SystemClass::C1 => Some(62),                  // Nothing bigger than a battlecruiser
+        SystemClass::Thera => Some(1000),             // Small, medium or large, never capital
+        _ => None
+    }
+}
+
+// Bounds the jump mass of wormholes whose type isn't known from the classes
+// at either end and the types that can lead into each class
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpMassInference {
+    largest_into : HashMap<u32, u32> // Target class to the largest jump mass of any type leading there
+}
+
+impl JumpMassInference {
+    pub fn new(wormhole_types : &WormholeTypes) -> Self {
+        let mut largest_into = HashMap::<u32, u32>::new();
+        for wormhole_type in &wormhole_types.types {
+            let (Some(class), Some(jump_mass)) = (wormhole_type.target_class(), wormhole_type.max_jump_mass()) else { continue };
+            let largest = largest_into.entry(class as u32).or_default();
+            *largest = (*largest).max((jump_mass / 1_000_000) as u32);
+        }
+        JumpMassInference { largest_into }
+    }
+
+    // Upper bound for a hole from one class to another, entered through side. None when nothing limits it.
+    pub fn bound(&self, from : &SystemClass, to : &SystemClass, side : &WormholeSide) -> Option<u32> {
+        let into = |class : &SystemClass| self.largest_into.get(&(class.clone() as u32)).copied();
+
+        // The typed end leads into the class at the other end
+        let by_type = match side {
+            WormholeSide::Origin => into(to),
+            WormholeSide::K162 => into(from),
+            WormholeSide::Unknown => into(from).zip(into(to)).map(|(a, b)| a.max(b))
+        };
+
+        [by_type, class_limit(from), class_limit(to)].into_iter().flatten().min()
+    }
+}
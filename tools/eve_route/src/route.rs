@@ -0,0 +1,342 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::collections::HashSet;
+use chrono::NaiveDateTime;
+use petgraph::algo;
+use petgraph::graph::{Graph, NodeIndex, EdgeReference};
+use petgraph::visit::{EdgeRef, EdgeFiltered};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::cost::*;
+use crate::filter::*;
+use crate::journey::*;
+use crate::jump::*;
+use crate::landmarks::*;
+use crate::lead::*;
+use crate::mass::*;
+use crate::travel::*;
+
+// How to treat wormholes that the ship may or may not fit through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UnknownJumpMass {
+    Passable,  // Assume the ship fits
+    Penalised, // Assume the ship fits, adding the cost model's unknown jump mass penalty, or at least one jump
+    Impassable // Never use them
+}
+
+// Penalised holes cost this much extra even when the cost model has no penalty,
+// such as the shortest profile
+const MIN_UNKNOWN_JUMP_MASS_PENALTY : u32 = 1;
+
+// Paths rejected for expiring wormholes before Yen's algorithm gives up looking for more
+const MAX_REJECTED_PATHS : usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteRequest {
+    pub from_system : u32,
+    pub to_system : u32,
+    pub via_systems : Vec<u32>, // Visited in order, unless optimise_via_order is set
+    pub optimise_via_order : bool,
+    pub avoid_systems : Vec<u32>,
+    pub collapsed_wormholes : Vec<CollapsedWormhole>, // Treated as gone, either way through
+    pub ship_size : u32, // Zero allows any wormhole
+    pub unknown_jump_mass : UnknownJumpMass,
+    pub exclude_lowsec : bool,
+    pub exclude_nullsec : bool,
+    pub exclude_zarzakh : bool,
+    pub exclude_voc : bool,
+    pub exclude_eol : bool,
+    pub exclude_eve_scout : bool,
+    pub min_confidence : f64, // Wormholes trusted less than this are left out
+    pub cost_model : CostModel,
+    pub fleet : Option<Fleet>,
+    pub departure : Option<NaiveDateTime>, // Gives each hop an ETA when set
+    pub depart_now : bool, // RouteService departs at the time of each search instead, so answers given again stay current
+    pub travel_time : TravelTime,
+    pub exclude_expiring : bool, // Rejects paths reaching a wormhole after it likely expires, needs a departure
+    pub jump_drive : Option<JumpDrive> // Plans capital jumps instead of gates and wormholes
+}
+
+impl RouteRequest {
+    pub fn new(from_system : u32, to_system : u32) -> Self {
+        RouteRequest {
+            from_system,
+            to_system,
+            via_systems : vec![],
+            optimise_via_order : false,
+            avoid_systems : vec![],
+            collapsed_wormholes : vec![],
+            ship_size : 0,
+            unknown_jump_mass : UnknownJumpMass::Penalised,
+            exclude_lowsec : false,
+            exclude_nullsec : false,
+            exclude_zarzakh : false,
+            exclude_voc : false,
+            exclude_eol : false,
+            exclude_eve_scout : false,
+            min_confidence : 0.0,
+            cost_model : CostModel::shortest(),
+            fleet : None,
+            departure : None,
+            depart_now : false,
+            travel_time : TravelTime::default(),
+            exclude_expiring : false,
+            jump_drive : None
+        }
+    }
+
+    // A ship that fits through anything isn't penalised
+    pub fn cost(&self, system : &System, connection : &Connection) -> u32 {
+        let unknown_jump_mass = match self.unknown_jump_mass {
+            UnknownJumpMass::Penalised if self.ship_size > SMALLEST_JUMP_MASS => self.cost_model.unknown_jump_mass.max(MIN_UNKNOWN_JUMP_MASS_PENALTY),
+            _ => 0
+        };
+        CostModel { unknown_jump_mass, ..self.cost_model.clone() }.cost(system, connection)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteHop {
+    pub system : System,
+    pub connection : Connection,
+    pub cost : u32,
+    pub mass_verdict : Option<MassVerdict>, // Only for wormholes when a fleet is given
+    pub leg : usize, // Index into RouteResult::stops of the stop this leg started from
+    pub eta : Option<NaiveDateTime>, // Arrival in the system, when the request has a departure
+    pub fallbacks : Vec<Connection> // Other connections between the same systems, cheapest first
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteResult {
+    pub hops : Vec<RouteHop>, // Excludes the starting system
+    pub stops : Vec<System> // From system, via systems in visiting order, to system
+}
+
+impl RouteResult {
+    pub fn jumps(&self) -> usize {
+        self.hops.len()
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.hops.iter().map(|h| h.cost).sum()
+    }
+
+    pub fn wormholes(&self) -> impl Iterator<Item = (&System, &WormholeAttributes)> {
+        self.hops.iter().filter_map(|h| match &h.connection {
+            Connection::Wormhole(w) => Some((&h.system, w)),
+            Connection::Gate | Connection::Jump(_) => None
+        })
+    }
+
+    // Ends in an unscouted system, so only a guess until someone jumps the lead
+    pub fn speculative(&self) -> bool {
+        self.hops.iter().any(|h| is_lead_system(h.system.id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RouteError {
+    FromSystemFiltered,
+    ToSystemFiltered,
+    ViaSystemFiltered,
+    TooManyViaSystems,
+    NoPath,
+    WormholesExpire,
+    JumpNotAllowed,
+    NoPositions,
+    MissingEdge
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RouteError::FromSystemFiltered => write!(f, "From system not in graph. It was probably removed by the filtering rules."),
+            RouteError::ToSystemFiltered => write!(f, "To system not in graph. It was probably removed by the filtering rules."),
+            RouteError::ViaSystemFiltered => write!(f, "Via system not in graph. It was probably removed by the filtering rules."),
+            RouteError::TooManyViaSystems => write!(f, "Too many via systems to optimise the visiting order. The limit is {}.", MAX_OPTIMISED_VIA_SYSTEMS),
+            RouteError::NoPath => write!(f, "No path between the systems"),
+            RouteError::WormholesExpire => write!(f, "No path reaches its wormholes before they are likely to expire"),
+            RouteError::JumpNotAllowed => write!(f, "Jump drives can only be used between lowsec and nullsec systems"),
+            RouteError::NoPositions => write!(f, "Static data has no system positions. The SDE needs regenerating for jump drive routes."),
+            RouteError::MissingEdge => write!(f, "Cannot find edge connecting nodes in graph")
+        }
+    }
+}
+
+// Removes wormholes that are likely to expire before even the fastest path reaches them
+pub(crate) fn filter_expiring(graph : &mut Graph<System, Connection>, start : NodeIndex, departure : NaiveDateTime, travel_time : &TravelTime) {
+    let arrivals = earliest_arrivals(graph, start, departure, travel_time);
+    graph.retain_edges(|g, e| {
+        let Some((source, _)) = g.edge_endpoints(e) else { return false };
+        match arrivals.get(&source) {
+            Some(arrival) => open_at(&g[e], *arrival + travel_time.warp(&g[source])),
+            None => true
+        }
+    });
+}
+
+pub(crate) fn edge_cost<'a>(graph : &'a Graph<System, Connection>, request : &'a RouteRequest) -> impl Fn(EdgeReference<Connection>) -> u32 + Copy + 'a {
+    move |e| request.cost(&graph[e.target()], e.weight())
+}
+
+// Whether a path reaches each of its wormholes in time, when the request asks for that
+pub(crate) fn on_time(graph : &Graph<System, Connection>, path : &[NodeIndex], request : &RouteRequest) -> bool {
+    match (request.departure, request.exclude_expiring) {
+        (Some(departure), true) => path_times(graph, path, departure, &request.travel_time, true).is_some(),
+        _ => true
+    }
+}
+
+pub(crate) fn hops_from_path(graph : &Graph<System, Connection>, path : Vec<NodeIndex>, request : &RouteRequest, leg : usize, departure : Option<NaiveDateTime>) -> Result<Vec<RouteHop>, RouteError> {
+    let times = match departure {
+        Some(departure) => Some(path_times(graph, &path, departure, &request.travel_time, request.exclude_expiring).ok_or(RouteError::WormholesExpire)?),
+        None => None
+    };
+
+    path.into_iter().tuple_windows::<(_,_)>().enumerate().map(|(i, (n1, n2))| {
+        let system = graph[n2].clone();
+
+        // Parallel edges come from the same hole reported by two sources, two holes between
+        // the same systems, or a hole alongside a gate. Gates win ties, they never collapse.
+        let reached = times.as_ref().map(|t| t[i] + request.travel_time.warp(&graph[n1]));
+        let mut connections = graph.edges_connecting(n1, n2)
+            .map(|e| e.weight().clone())
+            .sorted_by_key(|c| (
+                request.exclude_expiring && reached.is_some_and(|r| !open_at(c, r)),
+                request.cost(&system, c),
+                matches!(c, Connection::Wormhole(_))
+            ));
+        let connection = connections.next().ok_or(RouteError::MissingEdge)?;
+        let fallbacks = connections.collect();
+
+        let cost = request.cost(&system, &connection);
+        let mass_verdict = match (&connection, &request.fleet) {
+            (Connection::Wormhole(wormhole), Some(fleet)) => Some(mass_verdict(wormhole, fleet)),
+            _ => None
+        };
+        let eta = times.as_ref().map(|t| t[i + 1]);
+        Ok(RouteHop { system, connection, cost, mass_verdict, leg, eta, fallbacks })
+    }).collect()
+}
+
+pub fn find_route(graph : &Graph<System, Connection>, request : &RouteRequest) -> Result<RouteResult, RouteError> {
+    find_routes(graph, request, 1).map(|mut routes| routes.remove(0))
+}
+
+// Journeys with via systems and jump drive routes only ever produce a single route
+pub fn find_routes(graph : &Graph<System, Connection>, request : &RouteRequest, k : usize) -> Result<Vec<RouteResult>, RouteError> {
+    find_routes_filtered(&FilteredGraph::new(graph, request), None, request, k)
+}
+
+// Same routes as find_routes, but the searches are guided by the landmarks
+pub fn find_routes_with_landmarks(graph : &Graph<System, Connection>, landmarks : &Landmarks, request : &RouteRequest, k : usize) -> Result<Vec<RouteResult>, RouteError> {
+    find_routes_filtered(&FilteredGraph::new(graph, request), Some(landmarks), request, k)
+}
+
+// The graph must already have the request's filters applied
+pub fn find_routes_filtered(filtered : &FilteredGraph, landmarks : Option<&Landmarks>, request : &RouteRequest, k : usize) -> Result<Vec<RouteResult>, RouteError> {
+    if let Some(drive) = &request.jump_drive {
+        return Ok(vec![find_jump_route(filtered, request, drive)?]);
+    }
+
+    let from_system_node = filtered.node(request.from_system).ok_or(RouteError::FromSystemFiltered)?;
+    let to_system_node = filtered.node(request.to_system).ok_or(RouteError::ToSystemFiltered)?;
+
+    if !request.via_systems.is_empty() {
+        return Ok(vec![find_journey(filtered, landmarks, from_system_node, to_system_node, request)?]);
+    }
+
+    // Depends on the departure, so it can't be shared like the other filters
+    let filtered_graph = match (request.departure, request.exclude_expiring) {
+        (Some(departure), true) => {
+            let mut graph = filtered.graph.clone();
+            filter_expiring(&mut graph, from_system_node, departure, &request.travel_time);
+            Cow::Owned(graph)
+        },
+        _ => Cow::Borrowed(&filtered.graph)
+    };
+    let filtered_graph = filtered_graph.as_ref();
+
+    let stops = vec![filtered_graph[from_system_node].clone(), filtered_graph[to_system_node].clone()];
+
+    k_shortest_paths(filtered_graph, landmarks, from_system_node, to_system_node, request, k)?
+        .into_iter()
+        .map(|path| Ok(RouteResult { hops : hops_from_path(filtered_graph, path, request, 0, request.departure)?, stops : stops.clone() }))
+        .collect()
+}
+
+// Yen's algorithm for the k cheapest loop-free paths, cheapest first. Paths
+// that are not on time still seed the search for the next cheapest.
+fn k_shortest_paths(filtered_graph : &Graph<System, Connection>, landmarks : Option<&Landmarks>, from_system_node : NodeIndex, to_system_node : NodeIndex, request : &RouteRequest, k : usize) -> Result<Vec<Vec<NodeIndex>>, RouteError> {
+    let edge_cost = edge_cost(filtered_graph, request);
+
+    // Spur graphs only remove edges, so the bounds still hold for them
+    let bounds = LowerBounds::new(filtered_graph, landmarks, request, to_system_node);
+    let heuristic = |n : NodeIndex| bounds.bound(n);
+
+    let hop_cost = |n1 : NodeIndex, n2 : NodeIndex| filtered_graph.edges_connecting(n1, n2).map(edge_cost).min().unwrap_or(0);
+
+    let shortest = algo::astar(
+        filtered_graph,
+        from_system_node,
+        |n| n == to_system_node,
+        edge_cost,
+        heuristic,
+    ).ok_or(RouteError::NoPath)?;
+
+    let mut accepted = vec![shortest];
+    let mut candidates = Vec::<(u32, Vec<NodeIndex>)>::new();
+
+    let on_time_count = |accepted : &[(u32, Vec<NodeIndex>)]| accepted.iter().filter(|(_, p)| on_time(filtered_graph, p, request)).count();
+
+    while on_time_count(&accepted) < k && accepted.len() - on_time_count(&accepted) <= MAX_REJECTED_PATHS {
+        let (_, previous) = accepted.last().unwrap().clone();
+
+        for i in 0..previous.len().saturating_sub(1) {
+            let spur_node = previous[i];
+            let root = &previous[..=i];
+
+            let removed_edges = accepted.iter()
+                .filter(|(_, p)| p.len() > i + 1 && p[..=i] == *root)
+                .map(|(_, p)| (p[i], p[i + 1]))
+                .collect::<HashSet<_>>();
+
+            let removed_nodes = root[..i].iter().copied().collect::<HashSet<_>>();
+
+            let spur_graph = EdgeFiltered::from_fn(filtered_graph, |e : EdgeReference<Connection>| {
+                !removed_nodes.contains(&e.source()) &&
+                !removed_nodes.contains(&e.target()) &&
+                !removed_edges.contains(&(e.source(), e.target()))
+            });
+
+            let Some((spur_cost, spur_path)) = algo::astar(
+                &spur_graph,
+                spur_node,
+                |n| n == to_system_node,
+                edge_cost,
+                heuristic,
+            ) else { continue };
+
+            let root_cost : u32 = root.iter().tuple_windows::<(_,_)>().map(|(n1, n2)| hop_cost(*n1, *n2)).sum();
+            let path = root[..i].iter().copied().chain(spur_path).collect::<Vec<_>>();
+
+            if accepted.iter().chain(candidates.iter()).any(|(_, p)| *p == path) { continue }
+            candidates.push((root_cost + spur_cost, path));
+        }
+
+        let Some((best, _)) = candidates.iter().enumerate().min_by_key(|(_, (cost, path))| (*cost, path.len())) else { break };
+        accepted.push(candidates.swap_remove(best));
+    }
+
+    let paths = accepted.into_iter()
+        .map(|(_, path)| path)
+        .filter(|path| on_time(filtered_graph, path, request))
+        .take(k)
+        .collect::<Vec<_>>();
+
+    if paths.is_empty() { return Err(RouteError::WormholesExpire) }
+    Ok(paths)
+}
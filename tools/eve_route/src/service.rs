@@ -0,0 +1,167 @@
+use std::mem;
+use chrono::{NaiveDateTime, Utc};
+use petgraph::graph::Graph;
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::filter::*;
+use crate::graph::*;
+use crate::landmarks::*;
+use crate::lead::*;
+use crate::route::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RouteQuery {
+    Routes { request : RouteRequest, alternatives : usize, explore : Option<SystemClass> }, // Exploring finds the nearest system of the class instead
+    Leads { request : RouteRequest, max : usize },
+    Route { request : RouteRequest } // Only the best
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RouteAnswer {
+    Routes(Vec<RouteResult>),
+    Leads(Vec<Lead>),
+    Route(RouteResult)
+}
+
+impl RouteQuery {
+    pub fn request(&self) -> &RouteRequest {
+        match self {
+            RouteQuery::Routes { request, .. } | RouteQuery::Leads { request, .. } | RouteQuery::Route { request } => request
+        }
+    }
+
+    // The graph must already have the request's filters applied
+    fn answer(&self, filtered : &FilteredGraph, landmarks : Option<&Landmarks>, now : NaiveDateTime) -> Result<RouteAnswer, RouteError> {
+        let request = self.request();
+        let request = &RouteRequest { departure : request.depart_now.then_some(now).or(request.departure), ..request.clone() };
+        Ok(match self {
+            RouteQuery::Routes { explore : Some(class), .. } => RouteAnswer::Routes(vec![find_exploratory_route_filtered(filtered, request, class)?]),
+            RouteQuery::Routes { alternatives, explore : None, .. } => RouteAnswer::Routes(find_routes_filtered(filtered, landmarks, request, *alternatives)?),
+            RouteQuery::Leads { max, .. } => RouteAnswer::Leads(rank_leads_filtered(filtered, request)?.into_iter().filter(|l| l.chance > 0.0).take(*max).collect()),
+            RouteQuery::Route { .. } => RouteAnswer::Route(find_routes_filtered(filtered, landmarks, request, 1)?.remove(0))
+        })
+    }
+}
+
+// From the page to the routing worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WorkerRequest {
+    Systems(Vec<System>), // New static data, so the gates are rebuilt
+    Overlay(Vec<OverlayEdge>), // Replaces every wormhole
+    Query { id : u64, query : Box<RouteQuery> }, // Replaces the last query of the same kind
+    Cancel(u64)
+}
+
+// From the routing worker back to the page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum WorkerResponse {
+    Graph { systems : usize, wormholes : usize, millis : i64 },
+    Answer { id : u64, answer : Result<RouteAnswer, RouteError>, millis : i64 },
+    Cancelled(u64) // Replaced or cancelled before it was answered
+}
+
+struct StoredQuery {
+    id : u64,
+    query : Box<RouteQuery>,
+    answered : bool
+}
+
+// Enough for each kind of query to keep its own filters
+const MAX_FILTERED_GRAPHS : usize = 4;
+
+// The routing side of the worker. Messages are handled a batch at a time, so a
+// query replaced before its batch is worked through never runs. The last query
+// of each kind is kept and answered again whenever the graph changes.
+#[derive(Default)]
+pub struct RouteService {
+    base : Option<BaseGraph>,
+    landmarks : Option<Landmarks>,
+    overlay : Vec<OverlayEdge>,
+    graph : Option<Graph<System, Connection>>,
+    filtered : Vec<FilteredGraph>, // Most recently used last
+    queries : Vec<StoredQuery>
+}
+
+// Reuses a graph filtered the same way, or filters it again, dropping the least recently used
+fn filtered_for<'a>(filtered : &'a mut Vec<FilteredGraph>, graph : &Graph<System, Connection>, request : &RouteRequest) -> &'a FilteredGraph {
+    let reused = match filtered.iter().position(|f| f.matches(request)) {
+        Some(position) => filtered.remove(position),
+        None => FilteredGraph::new(graph, request)
+    };
+    if filtered.len() == MAX_FILTERED_GRAPHS { filtered.remove(0); }
+    filtered.push(reused);
+    filtered.last().unwrap()
+}
+
+fn millis_since(start : NaiveDateTime) -> i64 {
+    (Utc::now().naive_utc() - start).num_milliseconds()
+}
+
+impl RouteService {
+    // Number of filtered graphs kept for reuse
+    pub fn filtered_graphs(&self) -> usize {
+        self.filtered.len()
+    }
+
+    fn remove_query(&mut self, position : usize, responses : &mut Vec<WorkerResponse>) {
+        let query = self.queries.remove(position);
+        if !query.answered { responses.push(WorkerResponse::Cancelled(query.id)) }
+    }
+
+    pub fn handle(&mut self, requests : Vec<WorkerRequest>) -> Vec<WorkerResponse> {
+        let mut responses = Vec::<WorkerResponse>::new();
+        let mut changed = false;
+        let start = Utc::now().naive_utc();
+
+        for request in requests {
+            match request {
+                WorkerRequest::Systems(systems) => {
+                    let base = BaseGraph::new(&systems);
+                    self.landmarks = Some(Landmarks::new(&base.graph, DEFAULT_LANDMARKS));
+                    self.base = Some(base);
+                    changed = true;
+                },
+                WorkerRequest::Overlay(edges) => {
+                    if edges != self.overlay {
+                        self.overlay = edges;
+                        changed = true;
+                    }
+                },
+                WorkerRequest::Query { id, query } => {
+                    if let Some(position) = self.queries.iter().position(|q| mem::discriminant(q.query.as_ref()) == mem::discriminant(query.as_ref())) {
+                        self.remove_query(position, &mut responses);
+                    }
+                    self.queries.push(StoredQuery { id, query, answered : false });
+                },
+                WorkerRequest::Cancel(id) => {
+                    if let Some(position) = self.queries.iter().position(|q| q.id == id) {
+                        self.remove_query(position, &mut responses);
+                    }
+                }
+            }
+        }
+
+        if changed {
+            if let Some(base) = &self.base {
+                self.graph = Some(base.overlay(&self.overlay));
+                self.filtered.clear();
+                responses.push(WorkerResponse::Graph { systems : base.graph.node_count(), wormholes : self.overlay.len(), millis : millis_since(start) });
+                for query in &mut self.queries { query.answered = false }
+            }
+        }
+
+        // Queries wait for the static data
+        let Some(graph) = &self.graph else { return responses };
+        for query in self.queries.iter_mut().filter(|q| !q.answered) {
+            let start = Utc::now().naive_utc();
+            let filtered = filtered_for(&mut self.filtered, graph, query.query.request());
+            let answer = query.query.answer(filtered, self.landmarks.as_ref(), start);
+            responses.push(WorkerResponse::Answer { id : query.id, answer, millis : millis_since(start) });
+            query.answered = true;
+        }
+
+        responses
+    }
+}
@@ -0,0 +1,153 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use chrono::{NaiveDateTime, Duration};
+use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use eve_sde::*;
+
+use crate::connection::*;
+use crate::expiry::*;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TravelTime {
+    #[serde(with = "seconds")]
+    pub gate_jump : Duration,     // Jump through a gate, including the session change and align
+    #[serde(with = "seconds")]
+    pub wormhole_jump : Duration, // Jump through a wormhole, including the session change and align
+    #[serde(with = "seconds")]
+    pub warp : Duration,          // Warp across a system from where we arrive to where we leave
+    #[serde(with = "system_seconds")]
+    pub system_warp : HashMap<u32, Duration> // Replaces warp for systems where the distance is known
+}
+
+// Chrono durations have no serde support, so they're kept as whole seconds
+mod seconds {
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S : Serializer>(duration : &Duration, serializer : S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(duration.num_seconds())
+    }
+
+    pub fn deserialize<'de, D : Deserializer<'de>>(deserializer : D) -> Result<Duration, D::Error> {
+        Ok(Duration::seconds(i64::deserialize(deserializer)?))
+    }
+}
+
+mod system_seconds {
+    use std::collections::HashMap;
+    use chrono::Duration;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S : Serializer>(durations : &HashMap<u32, Duration>, serializer : S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(durations.iter().map(|(id, duration)| (id, duration.num_seconds())))
+    }
+
+    pub fn deserialize<'de, D : Deserializer<'de>>(deserializer : D) -> Result<HashMap<u32, Duration>, D::Error> {
+        Ok(HashMap::<u32, i64>::deserialize(deserializer)?.into_iter().map(|(id, seconds)| (id, Duration::seconds(seconds))).collect())
+    }
+}
+
+impl Default for TravelTime {
+    fn default() -> Self {
+        TravelTime {
+            gate_jump : Duration::seconds(20),
+            wormhole_jump : Duration::seconds(30),
+            warp : Duration::seconds(40),
+            system_warp : HashMap::new()
+        }
+    }
+}
+
+pub const METRES_PER_AU : f64 = 149_597_870_700.0;
+
+// AU/s, about a cruiser
+pub const DEFAULT_WARP_SPEED : f64 = 3.0;
+
+// Time in warp over a distance in metres. Speed grows exponentially at warp_speed
+// per second up to warp_speed AU/s, then falls at up to 2 per second to 100m/s.
+pub fn warp_duration(distance : f64, warp_speed : f64) -> Duration {
+    let accel = warp_speed.max(0.1);
+    let decel = (accel / 3.0).min(2.0);
+    let max_speed = accel * METRES_PER_AU;
+    let speeding = max_speed / accel + max_speed / decel;
+    let seconds = match distance < speeding {
+        true => {
+            // Never reaches full speed, so turns around part way
+            let peak = (distance / (1.0 / accel + 1.0 / decel)).max(100.0);
+            peak.ln() / accel + (peak / 100.0).ln() / decel
+        },
+        false => max_speed.ln() / accel + (max_speed / 100.0).ln() / decel + (distance - speeding) / max_speed
+    };
+    Duration::seconds(seconds.round() as i64)
+}
+
+// Warp across each system with at least two gates, over the mean distance
+// between them, for TravelTime::system_warp
+pub fn system_warps(systems : &[System], warp_speed : f64) -> HashMap<u32, Duration> {
+    systems.iter().filter(|s| s.gates.len() >= 2).map(|system| {
+        let distances = system.gates.iter().enumerate()
+            .flat_map(|(i, a)| system.gates[i + 1..].iter().map(|b| a.position.distance(&b.position)))
+            .collect::<Vec<_>>();
+        let mean = distances.iter().sum::<f64>() / distances.len() as f64;
+        (system.id, warp_duration(mean, warp_speed))
+    }).collect()
+}
+
+impl TravelTime {
+    pub fn warp(&self, system : &System) -> Duration {
+        self.system_warp.get(&system.id).copied().unwrap_or(self.warp)
+    }
+
+    pub fn jump(&self, connection : &Connection) -> Duration {
+        match connection {
+            Connection::Gate => self.gate_jump,
+            Connection::Wormhole(_) => self.wormhole_jump,
+            Connection::Jump(_) => Duration::zero() // Waiting for the drive is part of the jump route
+        }
+    }
+}
+
+// Whether a connection is likely still open when we reach it
+pub(crate) fn open_at(connection : &Connection, at : NaiveDateTime) -> bool {
+    match connection {
+        Connection::Wormhole(wormhole) => likely_open(wormhole, at),
+        Connection::Gate | Connection::Jump(_) => true
+    }
+}
+
+// Arrival time in each system of a path, starting with the departure from the
+// first. With exclude_expiring, None if a wormhole on the path is likely to
+// expire before we reach it.
+pub(crate) fn path_times(graph : &Graph<System, Connection>, path : &[NodeIndex], departure : NaiveDateTime, travel_time : &TravelTime, exclude_expiring : bool) -> Option<Vec<NaiveDateTime>> {
+    let mut times = vec![departure];
+    for window in path.windows(2) {
+        let (n1, n2) = (window[0], window[1]);
+        let reached = *times.last().unwrap() + travel_time.warp(&graph[n1]);
+        let edge = graph.edges_connecting(n1, n2)
+            .filter(|e| !exclude_expiring || open_at(e.weight(), reached))
+            .min_by_key(|e| travel_time.jump(e.weight()))?;
+        times.push(reached + travel_time.jump(edge.weight()));
+    }
+    Some(times)
+}
+
+// Dijkstra on time, only using wormholes likely to be open when reached. Waiting
+// never helps, so this is the earliest any path can arrive in each system.
+pub(crate) fn earliest_arrivals(graph : &Graph<System, Connection>, start : NodeIndex, departure : NaiveDateTime, travel_time : &TravelTime) -> HashMap<NodeIndex, NaiveDateTime> {
+    let mut arrivals = HashMap::from([(start, departure)]);
+    let mut queue = BinaryHeap::from([Reverse((departure, start))]);
+    while let Some(Reverse((time, node))) = queue.pop() {
+        if arrivals[&node] < time { continue }
+        let reached = time + travel_time.warp(&graph[node]);
+        for edge in graph.edges(node) {
+            if !open_at(edge.weight(), reached) { continue }
+            let arrival = reached + travel_time.jump(edge.weight());
+            if arrivals.get(&edge.target()).is_some_and(|t| *t <= arrival) { continue }
+            arrivals.insert(edge.target(), arrival);
+            queue.push(Reverse((arrival, edge.target())));
+        }
+    }
+    arrivals
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+//     2 - 3
+//    /     \
+//   1 - 4 - 5
+//    \     /
+//     6 = 7     (6 = 7 is a destabilised wormhole)
+fn diamond() -> TestGraph {
+    let mut g = TestGraph::new(&[
+        (1, SystemClass::Highsec),
+        (2, SystemClass::Highsec),
+        (3, SystemClass::Highsec),
+        (4, SystemClass::Lowsec),
+        (5, SystemClass::Highsec),
+        (6, SystemClass::Highsec),
+        (7, SystemClass::C2),
+    ]);
+    g.gate(1, 2).gate(2, 3).gate(3, 5).gate(1, 4).gate(4, 5).gate(1, 6).gate(7, 5);
+    g.wormhole(6, 7, WormholeAttributes { mass : WormholeMass::Destab, jump_mass : Some(375), ..wormhole("ABC-123", "DEF-456") });
+    g
+}
+
+#[test]
+fn k_shortest_routes() {
+    let g = diamond();
+    let routes = find_routes(&g.graph, &RouteRequest::new(1, 5), 5).unwrap();
+    assert_eq!(routes.len(), 3);
+    assert_eq!(route_ids(&routes[0]), vec![4, 5]);
+    assert_eq!(routes[1].jumps(), 3);
+    assert_eq!(routes[2].jumps(), 3);
+    let mut rest = vec![route_ids(&routes[1]), route_ids(&routes[2])];
+    rest.sort();
+    assert_eq!(rest, vec![vec![2, 3, 5], vec![6, 7, 5]]);
+}
+
+#[test]
+fn routes_are_ordered_by_cost() {
+    let g = diamond();
+    let request = RouteRequest { cost_model : CostModel::safest(), ..RouteRequest::new(1, 5) };
+    let routes = find_routes(&g.graph, &request, 3).unwrap();
+    assert_eq!(routes.iter().map(|r| r.cost()).collect::<Vec<_>>(), vec![3, 4, 5]);
+    assert_eq!(route_ids(&routes[0]), vec![2, 3, 5]);
+    assert_eq!(route_ids(&routes[1]), vec![4, 5]);
+    assert_eq!(route_ids(&routes[2]), vec![6, 7, 5]);
+    assert_eq!(routes[2].wormholes().count(), 1);
+}
+
+#[test]
+fn routes_are_loop_free_and_unique() {
+    let g = diamond();
+    let routes = find_routes(&g.graph, &RouteRequest::new(1, 5), 10).unwrap();
+    for (i, route) in routes.iter().enumerate() {
+        let mut ids = route_ids(route);
+        ids.push(1);
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count);
+        assert!(routes[..i].iter().all(|r| route_ids(r) != route_ids(route)));
+    }
+}
+
+#[test]
+fn single_route_matches_find_route() {
+    let g = diamond();
+    let routes = find_routes(&g.graph, &RouteRequest::new(1, 5), 1).unwrap();
+    assert_eq!(routes, vec![find_route(&g.graph, &RouteRequest::new(1, 5)).unwrap()]);
+}
@@ -0,0 +1,86 @@
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use petgraph::graph::{Graph, NodeIndex};
+use eve_sde::*;
+use eve_route::*;
+
+pub fn system(id : u32, class : SystemClass) -> System {
+    let security = match class {
+        SystemClass::Highsec => 5,
+        SystemClass::Lowsec => 2,
+        _ => -5
+    };
+    System::from(SystemV1 { id, name : format!("S{id}"), security, class, neighbours : vec![] })
+}
+
+pub fn wormhole(signature : &str, other_signature : &str) -> WormholeAttributes {
+    WormholeAttributes {
+        signature : Some(signature.to_owned()),
+        other_signature : Some(other_signature.to_owned()),
+        wormhole_type : None,
+        side : WormholeSide::Unknown,
+        kind : WormholeKind::Unknown,
+        life : WormholeLife::Stable,
+        mass : WormholeMass::Stable,
+        jump_mass : None,
+        jump_mass_bound : None,
+        total_mass : None,
+        mass_regeneration : None,
+        expiry : None,
+        source : WormholeSource::Tripwire,
+        confidence : 1.0
+    }
+}
+
+pub struct TestGraph {
+    pub graph : Graph<System, Connection>,
+    pub node_index : HashMap<u32, NodeIndex>
+}
+
+impl TestGraph {
+    pub fn new(systems : &[(u32, SystemClass)]) -> Self {
+        let mut graph = Graph::<System, Connection>::new();
+        let mut node_index = HashMap::<u32, NodeIndex>::new();
+        for (id, class) in systems {
+            node_index.insert(*id, graph.add_node(system(*id, class.clone())));
+        }
+        TestGraph { graph, node_index }
+    }
+
+    pub fn gate(&mut self, a : u32, b : u32) -> &mut Self {
+        self.connect(a, b, Connection::Gate, Connection::Gate)
+    }
+
+    pub fn wormhole(&mut self, a : u32, b : u32, attributes : WormholeAttributes) -> &mut Self {
+        let mut reverse = attributes.clone();
+        reverse.signature = attributes.other_signature.clone();
+        reverse.other_signature = attributes.signature.clone();
+        reverse.side = attributes.side.reverse();
+        self.connect(a, b, Connection::Wormhole(attributes), Connection::Wormhole(reverse))
+    }
+
+    // Position in light years
+    pub fn place(&mut self, id : u32, x : f64, y : f64, z : f64) -> &mut Self {
+        let light_year = METRES_PER_LIGHT_YEAR;
+        self.graph[self.node_index[&id]].position = Some(Position { x : x * light_year, y : y * light_year, z : z * light_year });
+        self
+    }
+
+    pub fn lead(&mut self, a : u32, class : SystemClass, attributes : WormholeAttributes) -> &mut Self {
+        let index_a = self.node_index[&a];
+        add_lead(&mut self.graph, &mut self.node_index, index_a, &class, attributes);
+        self
+    }
+
+    fn connect(&mut self, a : u32, b : u32, forward : Connection, reverse : Connection) -> &mut Self {
+        let (index_a, index_b) = (self.node_index[&a], self.node_index[&b]);
+        self.graph.add_edge(index_a, index_b, forward);
+        self.graph.add_edge(index_b, index_a, reverse);
+        self
+    }
+}
+
+pub fn route_ids(result : &RouteResult) -> Vec<u32> {
+    result.hops.iter().map(|h| h.system.id).collect()
+}
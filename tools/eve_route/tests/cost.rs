@@ -0,0 +1,69 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+// 1 - 2 - 3 - 4 by highsec gates, or 1 => 5 => 4 through an EOL, VOC chain
+fn risky_shortcut() -> TestGraph {
+    let mut g = TestGraph::new(&[
+        (1, SystemClass::Highsec),
+        (2, SystemClass::Highsec),
+        (3, SystemClass::Highsec),
+        (4, SystemClass::Highsec),
+        (5, SystemClass::C2),
+    ]);
+    let risky = WormholeAttributes { life : WormholeLife::EOL, mass : WormholeMass::VOC, jump_mass : Some(375), ..wormhole("ABC-123", "DEF-456") };
+    g.gate(1, 2).gate(2, 3).gate(3, 4).wormhole(1, 5, risky.clone()).wormhole(5, 4, risky);
+    g
+}
+
+#[test]
+fn shortest_takes_risky_shortcut() {
+    let g = risky_shortcut();
+    let result = find_route(&g.graph, &RouteRequest::new(1, 4)).unwrap();
+    assert_eq!(route_ids(&result), vec![5, 4]);
+    assert_eq!(result.cost(), 2);
+}
+
+#[test]
+fn safest_prefers_clean_gates() {
+    let g = risky_shortcut();
+    let request = RouteRequest { cost_model : CostModel::safest(), ..RouteRequest::new(1, 4) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4]);
+    assert_eq!(result.cost(), 3);
+}
+
+#[test]
+fn hop_costs() {
+    let model = CostModel::safest();
+    assert_eq!(model.cost(&system(1, SystemClass::Highsec), &Connection::Gate), 1);
+    assert_eq!(model.cost(&system(1, SystemClass::Lowsec), &Connection::Gate), 3);
+    assert_eq!(model.cost(&system(1, SystemClass::Nullsec), &Connection::Gate), 4);
+
+    let unknown = wormhole("ABC-123", "DEF-456");
+    assert_eq!(model.cost(&system(1, SystemClass::C3), &Connection::Wormhole(unknown.clone())), 2);
+
+    let destab_eve_scout = WormholeAttributes { mass : WormholeMass::Destab, source : WormholeSource::EveScout, jump_mass : Some(375), ..unknown.clone() };
+    assert_eq!(model.cost(&system(1, SystemClass::Nullsec), &Connection::Wormhole(destab_eve_scout)), 7);
+
+    let half_trusted = WormholeAttributes { confidence : 0.5, jump_mass : Some(375), ..unknown };
+    assert_eq!(model.cost(&system(1, SystemClass::C3), &Connection::Wormhole(half_trusted)), 3);
+}
+
+#[test]
+fn custom_nullsec_penalty() {
+    let mut g = TestGraph::new(&[
+        (1, SystemClass::Highsec),
+        (2, SystemClass::Nullsec),
+        (3, SystemClass::Lowsec),
+        (4, SystemClass::Lowsec),
+        (5, SystemClass::Highsec),
+    ]);
+    g.gate(1, 2).gate(2, 5).gate(1, 3).gate(3, 4).gate(4, 5);
+    let cost_model = CostModel { nullsec : 10, ..CostModel::shortest() };
+    let result = find_route(&g.graph, &RouteRequest { cost_model, ..RouteRequest::new(1, 5) }).unwrap();
+    assert_eq!(route_ids(&result), vec![3, 4, 5]);
+    assert_eq!(result.hops.iter().map(|h| h.cost).collect::<Vec<_>>(), vec![1, 1, 1]);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use chrono::{NaiveDate, NaiveDateTime, Duration};
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+fn now() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 12, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+}
+
+#[test]
+fn stable_expiry() {
+    let first_seen = now() - Duration::hours(6);
+    let expiry = estimate_expiry(first_seen, Some(Duration::hours(16)), &WormholeLife::Stable, now());
+    assert_eq!(expiry.earliest, now() + Duration::hours(4));
+    assert_eq!(expiry.latest, now() + Duration::hours(10));
+    assert_eq!(expiry.likely(), now() + Duration::hours(7));
+
+    // Unknown types fall back to the default lifetime
+    let expiry = estimate_expiry(first_seen, None, &WormholeLife::Stable, now());
+    assert_eq!(expiry.latest, first_seen + Duration::hours(DEFAULT_LIFETIME_HOURS));
+}
+
+#[test]
+fn eol_expiry() {
+    let expiry = estimate_expiry(now() - Duration::hours(2), Some(Duration::hours(24)), &WormholeLife::EOL, now());
+    assert_eq!(expiry, ExpiryWindow { earliest : now(), latest : now() + Duration::hours(4) });
+
+    // Close to the end of its lifetime
+    let expiry = estimate_expiry(now() - Duration::hours(23), Some(Duration::hours(24)), &WormholeLife::EOL, now());
+    assert_eq!(expiry.latest, now() + Duration::hours(1));
+}
+
+#[test]
+fn overdue_wormholes_expire_now() {
+    let expiry = estimate_expiry(now() - Duration::hours(30), Some(Duration::hours(24)), &WormholeLife::Stable, now());
+    assert_eq!(expiry, ExpiryWindow { earliest : now(), latest : now() });
+}
+
+#[test]
+fn remaining_hours() {
+    let expiry = remaining_hours_expiry(now(), 3);
+    assert_eq!(expiry.earliest, now() + Duration::hours(3));
+    assert_eq!(expiry.latest, now() + Duration::hours(4));
+}
+
+#[test]
+fn update_confidence_halves_once_overdue() {
+    let distrust_after = Some(Duration::hours(2));
+    assert_eq!(update_confidence(now() - Duration::hours(1), now(), distrust_after), 1.0);
+    assert_eq!(update_confidence(now() - Duration::hours(4), now(), distrust_after), 0.5);
+    assert_eq!(update_confidence(now() - Duration::hours(6), now(), distrust_after), 0.25);
+    assert_eq!(update_confidence(now() - Duration::hours(48), now(), None), 1.0);
+}
+
+#[test]
+fn expiring_wormholes_are_excluded() {
+    // 1 - 2 - ... - 6 by gates, then 6 => 7 through a wormhole expiring in about an hour, and 1 => 7 by a long gate chain
+    let mut g = TestGraph::new(&(1..=12).map(|id| (id, SystemClass::Nullsec)).collect::<Vec<_>>());
+    g.gate(1, 2).gate(2, 3).gate(3, 4).gate(4, 5).gate(5, 6);
+    g.gate(1, 8).gate(8, 9).gate(9, 10).gate(10, 11).gate(11, 12).gate(12, 7);
+    let expiry = Some(ExpiryWindow { earliest : now(), latest : now() + Duration::hours(2) });
+    g.wormhole(6, 7, WormholeAttributes { expiry, ..wormhole("ABC-123", "DEF-456") });
+
+    let result = find_route(&g.graph, &RouteRequest::new(1, 7)).unwrap();
+    assert_eq!(result.jumps(), 6);
+
+    let travel_time = TravelTime { gate_jump : Duration::minutes(3), warp : Duration::minutes(2), ..TravelTime::default() };
+    let request = RouteRequest { departure : Some(now()), exclude_expiring : true, travel_time, ..RouteRequest::new(1, 7) };
+    assert_eq!(find_route(&g.graph, &request).unwrap().jumps(), 6);
+
+    let travel_time = TravelTime { gate_jump : Duration::minutes(10), warp : Duration::minutes(5), ..TravelTime::default() };
+    let request = RouteRequest { travel_time, ..request };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![8, 9, 10, 11, 12, 7]);
+    assert!(result.wormholes().next().is_none());
+}
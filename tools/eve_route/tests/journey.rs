@@ -0,0 +1,68 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+// A highsec line 1 - 2 - 3 - 4 - 5 - 6
+fn line() -> TestGraph {
+    let mut g = TestGraph::new(&(1..=6).map(|id| (id, SystemClass::Highsec)).collect::<Vec<_>>());
+    g.gate(1, 2).gate(2, 3).gate(3, 4).gate(4, 5).gate(5, 6);
+    g
+}
+
+fn legs(result : &RouteResult) -> Vec<usize> {
+    result.hops.iter().map(|h| h.leg).collect()
+}
+
+#[test]
+fn via_systems_in_order() {
+    let g = line();
+    let request = RouteRequest { via_systems : vec![4, 2], ..RouteRequest::new(1, 6) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4, 3, 2, 3, 4, 5, 6]);
+    assert_eq!(legs(&result), vec![0, 0, 0, 1, 1, 2, 2, 2, 2]);
+    assert_eq!(result.stops.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 4, 2, 6]);
+}
+
+#[test]
+fn optimised_via_order() {
+    let g = line();
+    let request = RouteRequest { via_systems : vec![5, 4, 2], optimise_via_order : true, ..RouteRequest::new(1, 6) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4, 5, 6]);
+    assert_eq!(legs(&result), vec![0, 1, 1, 2, 3]);
+    assert_eq!(result.stops.iter().map(|s| s.id).collect::<Vec<_>>(), vec![1, 2, 4, 5, 6]);
+}
+
+#[test]
+fn optimised_order_round_trip() {
+    // Star around 1, each arm a different length, returning home
+    let mut g = TestGraph::new(&(1..=7).map(|id| (id, SystemClass::Highsec)).collect::<Vec<_>>());
+    g.gate(1, 2).gate(2, 3).gate(1, 4).gate(4, 5).gate(3, 5).gate(1, 6).gate(6, 7);
+    let request = RouteRequest { via_systems : vec![7, 3, 5], optimise_via_order : true, ..RouteRequest::new(1, 1) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(result.jumps(), 9);
+    assert_eq!(result.stops.first().unwrap().id, 1);
+    assert_eq!(result.stops.last().unwrap().id, 1);
+}
+
+#[test]
+fn journey_errors() {
+    let g = line();
+    let request = RouteRequest { via_systems : vec![3], avoid_systems : vec![3], ..RouteRequest::new(1, 6) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::ViaSystemFiltered));
+
+    let request = RouteRequest { via_systems : vec![1; MAX_OPTIMISED_VIA_SYSTEMS + 1], optimise_via_order : true, ..RouteRequest::new(1, 6) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::TooManyViaSystems));
+
+    let request = RouteRequest { via_systems : vec![4], avoid_systems : vec![3], ..RouteRequest::new(1, 6) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPath));
+}
+
+#[test]
+fn journey_is_a_single_route() {
+    let g = line();
+    let request = RouteRequest { via_systems : vec![3], ..RouteRequest::new(1, 6) };
+    assert_eq!(find_routes(&g.graph, &request, 5).unwrap().len(), 1);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+fn drive(range : f64, objective : JumpObjective) -> JumpDrive {
+    JumpDrive { range, fuel_per_light_year : 1000.0, fatigue_factor : 0.1, objective }
+}
+
+// Nullsec systems 1 to 5 four light years apart in a line, with highsec 6 and
+// wormhole system 7 between them, and lowsec 8 far off to the side
+fn line() -> TestGraph {
+    let mut g = TestGraph::new(&[
+        (1, SystemClass::Nullsec),
+        (2, SystemClass::Nullsec),
+        (3, SystemClass::Nullsec),
+        (4, SystemClass::Nullsec),
+        (5, SystemClass::Nullsec),
+        (6, SystemClass::Highsec),
+        (7, SystemClass::C3),
+        (8, SystemClass::Lowsec),
+    ]);
+    g.place(1, 0.0, 0.0, 0.0).place(2, 4.0, 0.0, 0.0).place(3, 8.0, 0.0, 0.0).place(4, 12.0, 0.0, 0.0).place(5, 16.0, 0.0, 0.0);
+    g.place(6, 2.0, 0.0, 0.0).place(7, 6.0, 0.0, 0.0).place(8, 8.0, 30.0, 0.0);
+    g
+}
+
+fn jumps(result : &RouteResult) -> Vec<JumpAttributes> {
+    result.hops.iter().map(|h| match &h.connection {
+        Connection::Jump(jump) => jump.clone(),
+        _ => panic!("Expected a jump")
+    }).collect()
+}
+
+#[test]
+fn fatigue_and_fuel() {
+    let jump = JumpDrive { fatigue_factor : 1.0, ..drive(5.0, JumpObjective::Jumps) }.jump(0.0, 5.0);
+    assert_eq!(jump, JumpAttributes { light_years : 5.0, fuel : 5000, fatigue : 60.0, reactivation : 6.0 });
+
+    // Fatigue and reactivation are capped
+    let jump = JumpDrive { fatigue_factor : 1.0, ..drive(5.0, JumpObjective::Jumps) }.jump(200.0, 5.0);
+    assert_eq!((jump.fatigue, jump.reactivation), (300.0, 30.0));
+}
+
+#[test]
+fn only_lowsec_and_nullsec() {
+    let g = line();
+    let request = RouteRequest { jump_drive : Some(drive(5.0, JumpObjective::Jumps)), ..RouteRequest::new(1, 5) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4, 5]);
+    assert!(jumps(&result).iter().all(|j| j.light_years == 4.0 && j.fuel == 4000));
+
+    for (from, to) in [(6, 5), (1, 7)] {
+        let request = RouteRequest { jump_drive : Some(drive(5.0, JumpObjective::Jumps)), ..RouteRequest::new(from, to) };
+        assert_eq!(find_route(&g.graph, &request), Err(RouteError::JumpNotAllowed));
+    }
+
+    let request = RouteRequest { jump_drive : Some(drive(5.0, JumpObjective::Jumps)), ..RouteRequest::new(1, 8) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPath));
+}
+
+#[test]
+fn fewest_jumps_or_least_fatigue() {
+    let g = line();
+    let request = RouteRequest { jump_drive : Some(drive(9.0, JumpObjective::Jumps)), ..RouteRequest::new(1, 5) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![3, 5]);
+
+    // Shorter jumps build less fatigue, even though there are more of them
+    let request = RouteRequest { jump_drive : Some(drive(9.0, JumpObjective::Fatigue)), ..RouteRequest::new(1, 5) };
+    let slow = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&slow), vec![2, 3, 4, 5]);
+    assert!(jumps(&slow).last().unwrap().fatigue < jumps(&result).last().unwrap().fatigue);
+}
+
+#[test]
+fn avoided_systems_and_positions() {
+    let g = line();
+    let request = RouteRequest { jump_drive : Some(drive(5.0, JumpObjective::Jumps)), avoid_systems : vec![3], ..RouteRequest::new(1, 5) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPath));
+
+    let g = TestGraph::new(&[(1, SystemClass::Nullsec), (2, SystemClass::Nullsec)]);
+    let request = RouteRequest { jump_drive : Some(drive(5.0, JumpObjective::Jumps)), ..RouteRequest::new(1, 2) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPositions));
+}
+
+// Least fatigue over every simple path, then fewest jumps, by trying them all
+fn least_fatigue(positions : &[(f64, f64)], drive : &JumpDrive, path : &mut Vec<usize>, fatigue : f64, best : &mut Option<(f64, usize)>) {
+    let last = *path.last().unwrap();
+    for next in 0..positions.len() {
+        if path.contains(&next) { continue }
+        let (dx, dy) = (positions[next].0 - positions[last].0, positions[next].1 - positions[last].1);
+        let light_years = (dx * dx + dy * dy).sqrt();
+        if light_years > drive.range { continue }
+        let jump = drive.jump(fatigue, light_years);
+        path.push(next);
+        if next == positions.len() - 1 {
+            let candidate = ((jump.fatigue * 60.0).round(), path.len() - 1);
+            if best.is_none_or(|b| candidate < b) { *best = Some(candidate) }
+        } else {
+            least_fatigue(positions, drive, path, (jump.fatigue - jump.reactivation).max(0.0), best);
+        }
+        path.pop();
+    }
+}
+
+#[test]
+fn least_fatigue_past_the_cap() {
+    // Every way to 7 reaches the cap, so the fewest jumps decide, even though
+    // a way with more jumps has less fatigue part way
+    let positions = [(1.75, 8.43), (5.35, 13.67), (1.92, 12.75), (10.43, 7.81), (2.15, 9.75), (9.50, 10.85), (7.83, 2.38)];
+    let mut g = TestGraph::new(&(1..=positions.len() as u32).map(|id| (id, SystemClass::Nullsec)).collect::<Vec<_>>());
+    for (id, (x, y)) in (1..).zip(positions) { g.place(id, x, y, 0.0); }
+    let drive = JumpDrive { fatigue_factor : 0.5, ..drive(8.0, JumpObjective::Fatigue) };
+
+    let request = RouteRequest { jump_drive : Some(drive.clone()), ..RouteRequest::new(1, positions.len() as u32) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(jumps(&result).last().unwrap().fatigue, 300.0);
+    assert_eq!(result.hops.len(), 3);
+
+    let mut best = None;
+    least_fatigue(&positions, &drive, &mut vec![0], 0.0, &mut best);
+    assert_eq!(best, Some((300.0 * 60.0, 3)));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use chrono::{Duration, NaiveDate};
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+const SIDE : u32 = 6;
+
+// A 6 x 6 grid of gates, ids 1 to 36 row by row, plus wormhole systems 40 and 41
+fn grid() -> TestGraph {
+    let mut systems = (1..=SIDE * SIDE).map(|id| (id, if id % 5 == 0 { SystemClass::Nullsec } else { SystemClass::Lowsec })).collect::<Vec<_>>();
+    systems.extend([(40, SystemClass::C2), (41, SystemClass::C3)]);
+    let mut g = TestGraph::new(&systems);
+    for row in 0..SIDE {
+        for col in 0..SIDE {
+            let id = row * SIDE + col + 1;
+            if col + 1 < SIDE { g.gate(id, id + 1); }
+            if row + 1 < SIDE { g.gate(id, id + SIDE); }
+        }
+    }
+    g
+}
+
+fn with_wormholes(mut g : TestGraph) -> TestGraph {
+    g.wormhole(1, 36, wormhole("ABC-123", "DEF-456"))
+        .wormhole(6, 40, WormholeAttributes { mass : WormholeMass::VOC, jump_mass : Some(62), ..wormhole("GHI-789", "JKL-012") })
+        .wormhole(40, 31, WormholeAttributes { life : WormholeLife::EOL, ..wormhole("MNO-345", "PQR-678") })
+        .wormhole(40, 41, wormhole("STU-901", "VWX-234"));
+    g
+}
+
+#[test]
+fn gate_bounds_are_admissible() {
+    let g = grid();
+    let landmarks = Landmarks::new(&g.graph, 4);
+    for a in 1..=SIDE * SIDE {
+        for b in 1..=SIDE * SIDE {
+            let (row_a, col_a, row_b, col_b) = ((a - 1) / SIDE, (a - 1) % SIDE, (b - 1) / SIDE, (b - 1) % SIDE);
+            let jumps = row_a.abs_diff(row_b) + col_a.abs_diff(col_b);
+            let bound = landmarks.gate_lower_bound(a, b).unwrap();
+            assert!(bound <= jumps, "{a} to {b}: bound {bound} exceeds {jumps} jumps");
+        }
+    }
+
+    // Wormhole space has no gates
+    assert_eq!(landmarks.gate_lower_bound(1, 40), None);
+}
+
+#[test]
+fn landmark_routes_match_plain_routes() {
+    let g = with_wormholes(grid());
+    let landmarks = Landmarks::new(&g.graph, DEFAULT_LANDMARKS);
+    let ids = (1..=SIDE * SIDE).chain([40, 41]).collect::<Vec<_>>();
+    for cost_model in [CostModel::shortest(), CostModel::safest()] {
+        for from in &ids {
+            for to in &ids {
+                if from == to { continue }
+                let request = RouteRequest { cost_model : cost_model.clone(), ..RouteRequest::new(*from, *to) };
+                let plain = find_routes(&g.graph, &request, 3).unwrap();
+                let guided = find_routes_with_landmarks(&g.graph, &landmarks, &request, 3).unwrap();
+                assert_eq!(
+                    plain.iter().map(|r| r.cost()).collect::<Vec<_>>(),
+                    guided.iter().map(|r| r.cost()).collect::<Vec<_>>(),
+                    "{from} to {to}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn landmark_journey_matches_plain_journey() {
+    let g = with_wormholes(grid());
+    let landmarks = Landmarks::new(&g.graph, DEFAULT_LANDMARKS);
+    let request = RouteRequest { via_systems : vec![41, 18, 3], optimise_via_order : true, ..RouteRequest::new(7, 30) };
+    let plain = find_route(&g.graph, &request).unwrap();
+    let guided = find_routes_with_landmarks(&g.graph, &landmarks, &request, 1).unwrap().remove(0);
+    assert_eq!(plain.cost(), guided.cost());
+    assert_eq!(plain.stops, guided.stops);
+}
+
+#[test]
+fn unreachable_system() {
+    let mut g = grid();
+    g.wormhole(40, 41, wormhole("ABC-123", "DEF-456"));
+    let landmarks = Landmarks::new(&g.graph, DEFAULT_LANDMARKS);
+    assert_eq!(find_routes_with_landmarks(&g.graph, &landmarks, &RouteRequest::new(1, 41), 1), Err(RouteError::NoPath));
+}
+
+#[test]
+fn wormhole_expiring_one_way() {
+    // 1 - 2 => 3 - 4 is shorter than 1 - 5 - 6 - 7 - 4, but the wormhole closes
+    // before it could be taken back, so only 2 => 3 is left
+    let mut g = TestGraph::new(&(1..=7).map(|id| (id, SystemClass::Nullsec)).collect::<Vec<_>>());
+    g.gate(1, 2).gate(3, 4).gate(1, 5).gate(5, 6).gate(6, 7).gate(7, 4);
+    let departure = NaiveDate::from_ymd_opt(2023, 12, 1).unwrap().and_hms_opt(12, 0, 0).unwrap();
+    let expiry = Some(ExpiryWindow { earliest : departure + Duration::seconds(150), latest : departure + Duration::seconds(150) });
+    g.wormhole(2, 3, WormholeAttributes { expiry, ..wormhole("ABC-123", "DEF-456") });
+
+    let landmarks = Landmarks::new(&g.graph, DEFAULT_LANDMARKS);
+    let request = RouteRequest { departure : Some(departure), exclude_expiring : true, ..RouteRequest::new(1, 4) };
+    let plain = find_route(&g.graph, &request).unwrap();
+    let guided = find_routes_with_landmarks(&g.graph, &landmarks, &request, 1).unwrap().remove(0);
+    assert_eq!(route_ids(&plain), vec![2, 3, 4]);
+    assert_eq!(route_ids(&guided), vec![2, 3, 4]);
+    assert_eq!(plain.cost(), guided.cost());
+}
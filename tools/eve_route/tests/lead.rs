@@ -0,0 +1,55 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+#[test]
+fn explore_through_lead() {
+    let mut g = TestGraph::new(&[(1, SystemClass::C3), (2, SystemClass::C3), (3, SystemClass::Highsec)]);
+    g.wormhole(1, 2, wormhole("ABC-123", "DEF-456"))
+        .wormhole(2, 3, wormhole("GHI-789", "JKL-012"))
+        .lead(1, SystemClass::Highsec, WormholeAttributes { jump_mass : Some(5), ..wormhole("MNO-345", "???") });
+
+    let result = find_exploratory_route(&g.graph, &RouteRequest::new(1, 1), &SystemClass::Highsec).unwrap();
+    assert_eq!(route_ids(&result), vec![lead_system_id(&SystemClass::Highsec)]);
+    assert!(result.speculative());
+
+    // Too big for the lead, so the scouted highsec system is the nearest
+    let request = RouteRequest { ship_size : 19, ..RouteRequest::new(1, 1) };
+    let result = find_exploratory_route(&g.graph, &request, &SystemClass::Highsec).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3]);
+    assert!(!result.speculative());
+}
+
+#[test]
+fn routes_never_pass_through_leads() {
+    let mut g = TestGraph::new(&[(1, SystemClass::C3), (2, SystemClass::C5)]);
+    g.lead(1, SystemClass::Highsec, wormhole("ABC-123", "???"))
+        .lead(2, SystemClass::Highsec, wormhole("DEF-456", "???"));
+
+    assert_eq!(find_route(&g.graph, &RouteRequest::new(1, 2)), Err(RouteError::NoPath));
+    assert_eq!(find_route(&g.graph, &RouteRequest::new(2, 1)), Err(RouteError::NoPath));
+}
+
+#[test]
+fn leads_ranked_by_expected_saving() {
+    let mut g = TestGraph::new(&[
+        (10, SystemClass::Highsec), (11, SystemClass::Highsec), (12, SystemClass::Highsec), (13, SystemClass::Highsec), (14, SystemClass::Highsec),
+        (20, SystemClass::C2)
+    ]);
+    g.gate(10, 11).gate(11, 12).gate(12, 13).gate(13, 14)
+        .wormhole(20, 10, wormhole("ABC-123", "DEF-456"))
+        .lead(20, SystemClass::Lowsec, wormhole("GHI-789", "???"))
+        .lead(20, SystemClass::Highsec, wormhole("JKL-012", "???"));
+
+    // Five jumps now. Through the lead, the far side saves 4, 3, 2, 1 or 0 jumps.
+    let leads = rank_leads(&g.graph, &RouteRequest::new(20, 14)).unwrap();
+    assert_eq!(leads.len(), 2);
+    assert_eq!(leads[0].class, SystemClass::Highsec);
+    assert_eq!(leads[0].wormhole.signature, Some("JKL-012".to_owned()));
+    assert_eq!(leads[0].chance, 0.8);
+    assert_eq!(leads[0].saving, 2.0);
+    assert_eq!(leads[1].class, SystemClass::Lowsec);
+    assert_eq!(leads[1].chance, 0.0);
+}
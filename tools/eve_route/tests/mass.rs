@@ -0,0 +1,116 @@
+mod common;
+
+use eve_sde::{SystemClass, WormholeType, WormholeTypes, WORMHOLE_TYPES_VERSION};
+use eve_route::*;
+use common::*;
+
+fn battleships(ship_count : u32, round_trip : bool) -> Fleet {
+    Fleet { ship_mass : 100.0, ship_count, round_trip, hours_before_return : 0.0 }
+}
+
+fn large_wormhole(mass : WormholeMass) -> WormholeAttributes {
+    WormholeAttributes { mass, jump_mass : Some(375), total_mass : Some(2000), ..wormhole("ABC-123", "DEF-456") }
+}
+
+#[test]
+fn remaining_mass_by_state() {
+    let (low, high) = remaining_mass(&large_wormhole(WormholeMass::Stable)).unwrap();
+    assert!((low - 900.0).abs() < 1e-6 && (high - 2200.0).abs() < 1e-6);
+    let (low, high) = remaining_mass(&large_wormhole(WormholeMass::VOC)).unwrap();
+    assert!(low.abs() < 1e-6 && (high - 220.0).abs() < 1e-6);
+    assert_eq!(remaining_mass(&wormhole("ABC-123", "DEF-456")), None);
+}
+
+#[test]
+fn verdicts() {
+    let stable = large_wormhole(WormholeMass::Stable);
+    assert_eq!(mass_verdict(&stable, &battleships(9, false)), MassVerdict::Safe);
+    assert_eq!(mass_verdict(&stable, &battleships(5, true)), MassVerdict::Risky);
+    assert_eq!(mass_verdict(&stable, &battleships(12, true)), MassVerdict::Collapse);
+    assert_eq!(mass_verdict(&large_wormhole(WormholeMass::Destab), &battleships(3, false)), MassVerdict::Risky);
+    assert_eq!(mass_verdict(&large_wormhole(WormholeMass::VOC), &battleships(3, false)), MassVerdict::Collapse);
+
+    let frigate = WormholeAttributes { jump_mass : Some(5), ..stable };
+    assert_eq!(mass_verdict(&frigate, &battleships(1, false)), MassVerdict::TooHeavy);
+    assert_eq!(mass_verdict(&wormhole("ABC-123", "DEF-456"), &battleships(1, false)), MassVerdict::Unknown);
+}
+
+#[test]
+fn regeneration_helps_return_trip() {
+    let regenerating = WormholeAttributes { mass_regeneration : Some(500), ..large_wormhole(WormholeMass::Stable) };
+    let fleet = battleships(5, true);
+    assert_eq!(mass_verdict(&regenerating, &fleet), MassVerdict::Risky);
+    let fleet = Fleet { hours_before_return : 24.0, ..fleet };
+    assert_eq!(mass_verdict(&regenerating, &fleet), MassVerdict::Safe);
+}
+
+#[test]
+fn route_hops_are_flagged() {
+    let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::Highsec), (3, SystemClass::C3)]);
+    g.gate(1, 2).wormhole(2, 3, large_wormhole(WormholeMass::VOC));
+    let request = RouteRequest { fleet : Some(battleships(3, true)), ..RouteRequest::new(1, 3) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(result.hops.iter().map(|h| h.mass_verdict.clone()).collect::<Vec<_>>(), vec![None, Some(MassVerdict::Collapse)]);
+
+    let result = find_route(&g.graph, &RouteRequest::new(1, 3)).unwrap();
+    assert!(result.hops.iter().all(|h| h.mass_verdict.is_none()));
+}
+
+fn wormhole_type(name : &str, target_class : SystemClass, jump_mass : f64) -> WormholeType {
+    WormholeType {
+        name : name.to_owned(),
+        attributes : [("wormholeTargetSystemClass".to_owned(), target_class as u8 as f64), ("wormholeMaxJumpMass".to_owned(), jump_mass * 1e6)].into(),
+        static_in : vec![]
+    }
+}
+
+#[test]
+fn infer_jump_mass_from_classes() {
+    let inference = JumpMassInference::new(&WormholeTypes { version : WORMHOLE_TYPES_VERSION, types : vec![
+        wormhole_type("D382", SystemClass::C2, 375.0),
+        wormhole_type("O477", SystemClass::C3, 375.0),
+        wormhole_type("D845", SystemClass::Highsec, 1000.0),
+        wormhole_type("A009", SystemClass::C13, 5.0)
+    ]});
+
+    assert_eq!(inference.bound(&SystemClass::C2, &SystemClass::C3, &WormholeSide::Unknown), Some(375));
+    assert_eq!(inference.bound(&SystemClass::C3, &SystemClass::Highsec, &WormholeSide::Unknown), Some(1000));
+    assert_eq!(inference.bound(&SystemClass::Highsec, &SystemClass::C3, &WormholeSide::Origin), Some(375));
+    assert_eq!(inference.bound(&SystemClass::Highsec, &SystemClass::C3, &WormholeSide::K162), Some(1000));
+    assert_eq!(inference.bound(&SystemClass::C1, &SystemClass::Highsec, &WormholeSide::Unknown), Some(62));
+    assert_eq!(inference.bound(&SystemClass::Nullsec, &SystemClass::C13, &WormholeSide::Unknown), Some(5));
+    assert_eq!(inference.bound(&SystemClass::Nullsec, &SystemClass::Lowsec, &WormholeSide::Unknown), None);
+}
+
+#[test]
+fn unknown_jump_mass_policy() {
+    let mut g = TestGraph::new(&[(1, SystemClass::C2), (2, SystemClass::C2), (3, SystemClass::C2)]);
+    g.wormhole(1, 3, WormholeAttributes { jump_mass_bound : Some(375), ..wormhole("ABC-123", "DEF-456") })
+        .wormhole(1, 2, large_wormhole(WormholeMass::Stable))
+        .wormhole(2, 3, large_wormhole(WormholeMass::Stable));
+
+    let request = |ship_size, unknown_jump_mass, cost_model| RouteRequest { ship_size, unknown_jump_mass, cost_model, ..RouteRequest::new(1, 3) };
+
+    // Penalised by two, so the longer way round is cheaper
+    let penalised = CostModel { unknown_jump_mass : 2, ..CostModel::shortest() };
+    assert_eq!(route_ids(&find_route(&g.graph, &request(220, UnknownJumpMass::Penalised, penalised.clone())).unwrap()), vec![2, 3]);
+    assert_eq!(route_ids(&find_route(&g.graph, &request(220, UnknownJumpMass::Passable, penalised.clone())).unwrap()), vec![3]);
+    assert_eq!(route_ids(&find_route(&g.graph, &request(220, UnknownJumpMass::Impassable, CostModel::shortest())).unwrap()), vec![2, 3]);
+
+    // Every hole fits a frigate, and nothing over the inferred bound fits
+    assert_eq!(route_ids(&find_route(&g.graph, &request(1, UnknownJumpMass::Impassable, CostModel::shortest())).unwrap()), vec![3]);
+    assert_eq!(find_route(&g.graph, &request(1000, UnknownJumpMass::Passable, CostModel::shortest())), Err(RouteError::NoPath));
+
+    // Penalised costs something under the shortest profile too, so an equally long known way wins
+    let mut g = TestGraph::new(&[(1, SystemClass::C2), (2, SystemClass::C2), (3, SystemClass::C2), (4, SystemClass::C2)]);
+    g.wormhole(1, 2, wormhole("ABC-123", "DEF-456"))
+        .wormhole(2, 4, large_wormhole(WormholeMass::Stable))
+        .wormhole(1, 3, large_wormhole(WormholeMass::Stable))
+        .wormhole(3, 4, large_wormhole(WormholeMass::Stable));
+    let request = |unknown_jump_mass| RouteRequest { ship_size : 220, unknown_jump_mass, ..RouteRequest::new(1, 4) };
+    assert_eq!(route_ids(&find_route(&g.graph, &request(UnknownJumpMass::Penalised)).unwrap()), vec![3, 4]);
+    assert_eq!(find_route(&g.graph, &request(UnknownJumpMass::Penalised)).unwrap().hops.iter().map(|h| h.cost).sum::<u32>(), 2);
+
+    let too_big = WormholeAttributes { jump_mass_bound : Some(62), ..wormhole("ABC-123", "DEF-456") };
+    assert_eq!(mass_verdict(&too_big, &battleships(1, false)), MassVerdict::TooHeavy);
+}
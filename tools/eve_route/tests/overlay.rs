@@ -0,0 +1,43 @@
+mod common;
+
+use eve_sde::*;
+use eve_route::*;
+use common::*;
+
+// 1 - 2 - 3 by gates
+fn base() -> BaseGraph {
+    let system = |id, neighbours| System::from(SystemV1 { id, name : format!("S{id}"), security : 5, class : SystemClass::Highsec, neighbours });
+    BaseGraph::new(&[system(1, vec![2]), system(2, vec![3]), system(3, vec![]), System { class : SystemClass::C3, ..system(4, vec![]) }])
+}
+
+#[test]
+fn overlay_keeps_gate_indices() {
+    let base = base();
+    let graph = base.overlay(&[
+        OverlayEdge { from : 1, to : OverlayTarget::System(4), wormhole : wormhole("ABC-123", "DEF-456") },
+        OverlayEdge { from : 4, to : OverlayTarget::System(1), wormhole : wormhole("DEF-456", "ABC-123") },
+        OverlayEdge { from : 3, to : OverlayTarget::Lead(SystemClass::C5), wormhole : wormhole("GHI-789", "???") }
+    ]);
+
+    for (id, n) in &base.node_index {
+        assert_eq!(graph[*n].id, *id);
+    }
+    assert_eq!(graph.edge_count(), base.graph.edge_count() + 3);
+    assert_eq!(graph.node_count(), base.graph.node_count() + 1);
+    assert_eq!(route_ids(&find_route(&graph, &RouteRequest::new(3, 4)).unwrap()), vec![2, 1, 4]);
+
+    // The base is left as it was
+    assert_eq!(base.graph.edge_count(), 4);
+}
+
+#[test]
+fn overlay_skips_unknown_systems() {
+    let base = base();
+    let graph = base.overlay(&[
+        OverlayEdge { from : 1, to : OverlayTarget::System(99), wormhole : wormhole("ABC-123", "DEF-456") },
+        OverlayEdge { from : 99, to : OverlayTarget::Lead(SystemClass::C5), wormhole : wormhole("GHI-789", "???") }
+    ]);
+    assert_eq!(graph.edge_count(), base.graph.edge_count());
+    assert_eq!(graph.node_count(), base.graph.node_count());
+    assert_eq!(base.system(2).map(|s| s.name.clone()), Some("S2".to_owned()));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+fn connection_source(connection : &Connection) -> Option<WormholeSource> {
+    match connection {
+        Connection::Wormhole(w) => Some(w.source.clone()),
+        _ => None
+    }
+}
+
+#[test]
+fn same_hole_from_two_sources() {
+    let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::Thera)]);
+    let tripwire = WormholeAttributes { jump_mass : Some(375), ..wormhole("ABC-123", "DEF-456") };
+    g.wormhole(1, 2, WormholeAttributes { source : WormholeSource::EveScout, ..tripwire.clone() }).wormhole(1, 2, tripwire);
+
+    let request = RouteRequest { cost_model : CostModel::safest(), ..RouteRequest::new(1, 2) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(result.jumps(), 1);
+    assert_eq!(connection_source(&result.hops[0].connection), Some(WormholeSource::Tripwire));
+    assert_eq!(result.hops[0].fallbacks.iter().map(connection_source).collect::<Vec<_>>(), vec![Some(WormholeSource::EveScout)]);
+
+    // Filtered connections are not offered as fallbacks
+    let request = RouteRequest { exclude_eve_scout : true, ..request };
+    assert!(find_route(&g.graph, &request).unwrap().hops[0].fallbacks.is_empty());
+}
+
+#[test]
+fn gate_beats_parallel_hole() {
+    let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::Highsec), (3, SystemClass::Highsec)]);
+    g.gate(1, 2).gate(2, 3).wormhole(1, 2, WormholeAttributes { jump_mass : Some(375), ..wormhole("ABC-123", "DEF-456") });
+
+    for cost_model in [CostModel::shortest(), CostModel::safest()] {
+        let result = find_route(&g.graph, &RouteRequest { cost_model, ..RouteRequest::new(1, 3) }).unwrap();
+        assert_eq!(route_ids(&result), vec![2, 3]);
+        assert_eq!(result.hops[0].connection, Connection::Gate);
+        assert_eq!(result.hops[0].fallbacks.len(), 1);
+        assert!(result.hops[1].fallbacks.is_empty());
+    }
+}
+
+#[test]
+fn cheapest_of_two_holes() {
+    let mut g = TestGraph::new(&[(1, SystemClass::C2), (2, SystemClass::C3)]);
+    g.wormhole(1, 2, WormholeAttributes { mass : WormholeMass::VOC, jump_mass : Some(375), ..wormhole("ABC-123", "DEF-456") })
+        .wormhole(1, 2, WormholeAttributes { jump_mass : Some(375), ..wormhole("GHI-789", "JKL-012") });
+
+    let request = RouteRequest { cost_model : CostModel::safest(), ..RouteRequest::new(1, 2) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(result.cost(), 1);
+    match &result.hops[0].connection {
+        Connection::Wormhole(w) => assert_eq!(w.signature.as_deref(), Some("GHI-789")),
+        _ => panic!("Expected wormhole")
+    }
+
+    // Alternatives are by path, so parallel holes do not show up as separate routes
+    assert_eq!(find_routes(&g.graph, &request, 5).unwrap().len(), 1);
+}
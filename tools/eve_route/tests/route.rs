@@ -0,0 +1,121 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+// 1 - 2 - 3 - 4 - 5 in highsec, with a lowsec detour 1 - 6 - 5
+fn chain() -> TestGraph {
+    let mut g = TestGraph::new(&[
+        (1, SystemClass::Highsec),
+        (2, SystemClass::Highsec),
+        (3, SystemClass::Highsec),
+        (4, SystemClass::Highsec),
+        (5, SystemClass::Highsec),
+        (6, SystemClass::Lowsec),
+        (7, SystemClass::C3),
+    ]);
+    g.gate(1, 2).gate(2, 3).gate(3, 4).gate(4, 5).gate(1, 6).gate(6, 5);
+    g
+}
+
+#[test]
+fn shortest_gate_route() {
+    let g = chain();
+    let result = find_route(&g.graph, &RouteRequest::new(1, 5)).unwrap();
+    assert_eq!(route_ids(&result), vec![6, 5]);
+    assert_eq!(result.jumps(), 2);
+    assert!(result.hops.iter().all(|h| h.connection == Connection::Gate));
+}
+
+#[test]
+fn same_system_is_empty_route() {
+    let g = chain();
+    let result = find_route(&g.graph, &RouteRequest::new(3, 3)).unwrap();
+    assert!(result.hops.is_empty());
+}
+
+#[test]
+fn exclude_lowsec() {
+    let g = chain();
+    let request = RouteRequest { exclude_lowsec : true, ..RouteRequest::new(1, 5) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn avoid_systems() {
+    let g = chain();
+    let request = RouteRequest { avoid_systems : vec![6], ..RouteRequest::new(1, 5) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 3, 4, 5]);
+}
+
+#[test]
+fn wormhole_shortcut() {
+    let mut g = chain();
+    g.wormhole(2, 7, wormhole("ABC-123", "DEF-456")).wormhole(7, 4, wormhole("GHI-789", "JKL-012"));
+    let request = RouteRequest { exclude_lowsec : true, ..RouteRequest::new(2, 4) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![7, 4]);
+    match &result.hops[0].connection {
+        Connection::Wormhole(w) => assert_eq!(w.signature.as_deref(), Some("ABC-123")),
+        _ => panic!("Expected wormhole")
+    }
+}
+
+#[test]
+fn collapsed_wormholes() {
+    let mut g = chain();
+    g.wormhole(2, 7, wormhole("ABC-123", "DEF-456")).wormhole(7, 4, wormhole("GHI-789", "JKL-012"));
+    let collapse = CollapsedWormhole::new(4, 7, &wormhole("JKL-012", "GHI-789"));
+    let request = RouteRequest { exclude_lowsec : true, collapsed_wormholes : vec![collapse.clone()], ..RouteRequest::new(2, 4) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![3, 4]);
+
+    // Another hole between the same systems stays open
+    g.wormhole(7, 4, wormhole("MNO-345", "PQR-678"));
+    let result = find_route(&g.graph, &request).unwrap();
+    assert_eq!(route_ids(&result), vec![7, 4]);
+}
+
+#[test]
+fn wormhole_filters() {
+    let attributes = WormholeAttributes { jump_mass : Some(62), ..wormhole("ABC-123", "DEF-456") };
+    let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::C3)]);
+    g.wormhole(1, 2, attributes.clone());
+    assert!(find_route(&g.graph, &RouteRequest { ship_size : 19, ..RouteRequest::new(1, 2) }).is_ok());
+    assert_eq!(find_route(&g.graph, &RouteRequest { ship_size : 220, ..RouteRequest::new(1, 2) }), Err(RouteError::NoPath));
+
+    let filters : [(WormholeAttributes, RouteRequest); 4] = [
+        (WormholeAttributes { mass : WormholeMass::VOC, ..attributes.clone() }, RouteRequest { exclude_voc : true, ..RouteRequest::new(1, 2) }),
+        (WormholeAttributes { life : WormholeLife::EOL, ..attributes.clone() }, RouteRequest { exclude_eol : true, ..RouteRequest::new(1, 2) }),
+        (WormholeAttributes { source : WormholeSource::EveScout, ..attributes.clone() }, RouteRequest { exclude_eve_scout : true, ..RouteRequest::new(1, 2) }),
+        (WormholeAttributes { confidence : 0.25, ..attributes.clone() }, RouteRequest { min_confidence : 0.5, ..RouteRequest::new(1, 2) }),
+    ];
+
+    for (attributes, request) in filters {
+        let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::C3)]);
+        g.wormhole(1, 2, attributes);
+        assert!(find_route(&g.graph, &RouteRequest::new(1, 2)).is_ok());
+        assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPath));
+    }
+}
+
+#[test]
+fn unknown_jump_mass_is_passable() {
+    let mut g = TestGraph::new(&[(1, SystemClass::Highsec), (2, SystemClass::C3)]);
+    g.wormhole(1, 2, wormhole("ABC-123", "DEF-456"));
+    let request = RouteRequest { ship_size : 1000, ..RouteRequest::new(1, 2) };
+    assert!(find_route(&g.graph, &request).is_ok());
+}
+
+#[test]
+fn endpoint_errors() {
+    let g = chain();
+    let request = RouteRequest { exclude_lowsec : true, ..RouteRequest::new(6, 1) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::FromSystemFiltered));
+    let request = RouteRequest { exclude_lowsec : true, ..RouteRequest::new(1, 6) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::ToSystemFiltered));
+    assert_eq!(find_route(&g.graph, &RouteRequest::new(1, 7)), Err(RouteError::NoPath));
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use chrono::{NaiveDate, Utc};
+use eve_sde::*;
+use eve_route::*;
+use common::*;
+
+// 1 - 2 - 3 - 4 by gates
+fn systems() -> Vec<System> {
+    let system = |id, neighbours| System::from(SystemV1 { id, name : format!("S{id}"), security : 5, class : SystemClass::Highsec, neighbours });
+    vec![system(1, vec![2]), system(2, vec![3]), system(3, vec![4]), system(4, vec![])]
+}
+
+fn route_query(id : u64, from_system : u32, to_system : u32) -> WorkerRequest {
+    WorkerRequest::Query { id, query : Box::new(RouteQuery::Route { request : RouteRequest::new(from_system, to_system) }) }
+}
+
+fn answers(responses : &[WorkerResponse]) -> Vec<(u64, Vec<u32>)> {
+    responses.iter().filter_map(|r| match r {
+        WorkerResponse::Answer { id, answer : Ok(RouteAnswer::Route(route)), .. } => Some((*id, route_ids(route))),
+        _ => None
+    }).collect()
+}
+
+#[test]
+fn queries_wait_for_systems() {
+    let mut service = RouteService::default();
+    assert!(service.handle(vec![route_query(1, 1, 4)]).is_empty());
+
+    let responses = service.handle(vec![WorkerRequest::Systems(systems())]);
+    assert!(matches!(responses[0], WorkerResponse::Graph { systems : 4, wormholes : 0, .. }));
+    assert_eq!(answers(&responses), vec![(1, vec![2, 3, 4])]);
+}
+
+#[test]
+fn replaced_queries_are_cancelled() {
+    let mut service = RouteService::default();
+    let responses = service.handle(vec![WorkerRequest::Systems(systems()), route_query(1, 1, 3), route_query(2, 1, 4)]);
+    assert!(responses.contains(&WorkerResponse::Cancelled(1)));
+    assert_eq!(answers(&responses), vec![(2, vec![2, 3, 4])]);
+
+    // Other kinds of query are kept
+    let leads = WorkerRequest::Query { id : 3, query : Box::new(RouteQuery::Leads { request : RouteRequest::new(1, 4), max : 5 }) };
+    let responses = service.handle(vec![leads, WorkerRequest::Cancel(3)]);
+    assert_eq!(responses, vec![WorkerResponse::Cancelled(3)]);
+    assert!(service.handle(vec![WorkerRequest::Cancel(2)]).is_empty());
+}
+
+#[test]
+fn answers_again_when_the_graph_changes() {
+    let mut service = RouteService::default();
+    service.handle(vec![WorkerRequest::Systems(systems()), route_query(1, 1, 4)]);
+
+    let overlay = vec![
+        OverlayEdge { from : 1, to : OverlayTarget::System(4), wormhole : wormhole("ABC-123", "DEF-456") },
+        OverlayEdge { from : 4, to : OverlayTarget::System(1), wormhole : wormhole("DEF-456", "ABC-123") }
+    ];
+    let responses = service.handle(vec![WorkerRequest::Overlay(overlay.clone())]);
+    assert_eq!(answers(&responses), vec![(1, vec![4])]);
+
+    // The same wormholes again change nothing
+    assert!(service.handle(vec![WorkerRequest::Overlay(overlay)]).is_empty());
+}
+
+#[test]
+fn departs_at_each_answer() {
+    let mut service = RouteService::default();
+    service.handle(vec![WorkerRequest::Systems(systems())]);
+    let request = RouteRequest { depart_now : true, ..RouteRequest::new(1, 4) };
+    let before = Utc::now().naive_utc();
+    let responses = service.handle(vec![WorkerRequest::Query { id : 1, query : Box::new(RouteQuery::Route { request }) }]);
+    let Some(WorkerResponse::Answer { answer : Ok(RouteAnswer::Route(route)), .. }) = responses.last() else { panic!("No route") };
+    assert!(route.hops.iter().all(|h| h.eta.is_some_and(|eta| eta >= before)));
+}
+
+#[test]
+fn shares_filtered_graphs() {
+    let mut service = RouteService::default();
+    let leads = WorkerRequest::Query { id : 2, query : Box::new(RouteQuery::Leads { request : RouteRequest::new(1, 4), max : 5 }) };
+    service.handle(vec![WorkerRequest::Systems(systems()), route_query(1, 1, 4), leads]);
+    assert_eq!(service.filtered_graphs(), 1);
+
+    // Other filters get their own
+    let request = RouteRequest { avoid_systems : vec![3], ..RouteRequest::new(1, 2) };
+    service.handle(vec![WorkerRequest::Query { id : 3, query : Box::new(RouteQuery::Routes { request, alternatives : 1, explore : None }) }]);
+    assert_eq!(service.filtered_graphs(), 2);
+
+    // A new graph filters again, so the wormhole is used
+    let responses = service.handle(vec![WorkerRequest::Overlay(vec![OverlayEdge { from : 1, to : OverlayTarget::System(4), wormhole : wormhole("ABC-123", "DEF-456") }])]);
+    assert_eq!(answers(&responses), vec![(1, vec![4])]);
+    assert_eq!(service.filtered_graphs(), 2);
+}
+
+#[test]
+fn messages_round_trip() {
+    let mut request = RouteRequest::new(1, 4);
+    request.departure = NaiveDate::from_ymd_opt(2024, 1, 1).and_then(|d| d.and_hms_opt(12, 0, 0));
+    request.travel_time.system_warp.insert(2, chrono::Duration::seconds(15));
+    let query = WorkerRequest::Query { id : 1, query : Box::new(RouteQuery::Routes { request, alternatives : 3, explore : Some(SystemClass::C5) }) };
+    let json = serde_json::to_string(&query).unwrap();
+    assert_eq!(serde_json::from_str::<WorkerRequest>(&json).unwrap(), query);
+
+    let mut service = RouteService::default();
+    for response in service.handle(vec![WorkerRequest::Systems(systems()), route_query(2, 1, 4)]) {
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(serde_json::from_str::<WorkerResponse>(&json).unwrap(), response);
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use eve_sde::SystemClass;
+use eve_route::*;
+use common::*;
+
+fn entries(result : &RouteResult) -> Vec<Option<String>> {
+    result.hops.iter().map(|h| match &h.connection {
+        Connection::Wormhole(w) => w.entry(),
+        _ => None
+    }).collect()
+}
+
+#[test]
+fn enter_through_either_end() {
+    // N110 spawned in the C1 and opened a K162 in highsec
+    let mut g = TestGraph::new(&[(1, SystemClass::C1), (2, SystemClass::Highsec)]);
+    g.wormhole(1, 2, WormholeAttributes {
+        wormhole_type : Some("N110".to_owned()),
+        side : WormholeSide::Origin,
+        kind : WormholeKind::Static,
+        ..wormhole("ABC-123", "DEF-456")
+    });
+
+    let outbound = find_route(&g.graph, &RouteRequest::new(1, 2)).unwrap();
+    assert_eq!(entries(&outbound), vec![Some("N110".to_owned())]);
+
+    let inbound = find_route(&g.graph, &RouteRequest::new(2, 1)).unwrap();
+    assert_eq!(entries(&inbound), vec![Some("K162".to_owned())]);
+    let Connection::Wormhole(w) = &inbound.hops[0].connection else { panic!("Expected a wormhole") };
+    assert_eq!(w.wormhole_type, Some("N110".to_owned()));
+    assert_eq!(w.kind, WormholeKind::Static);
+}
+
+#[test]
+fn unknown_side() {
+    let mut g = TestGraph::new(&[(1, SystemClass::C1), (2, SystemClass::Highsec)]);
+    g.wormhole(1, 2, WormholeAttributes { wormhole_type : Some("N110".to_owned()), ..wormhole("ABC-123", "DEF-456") });
+    assert_eq!(entries(&find_route(&g.graph, &RouteRequest::new(2, 1)).unwrap()), vec![None]);
+}
+
+#[test]
+fn static_or_wandering() {
+    let c1_statics = [SystemClass::C1];
+    assert_eq!(WormholeKind::classify(Some(&c1_statics), Some(&SystemClass::C1)), WormholeKind::Static);
+    assert_eq!(WormholeKind::classify(Some(&c1_statics), Some(&SystemClass::C3)), WormholeKind::Wandering);
+    assert_eq!(WormholeKind::classify(Some(&[]), Some(&SystemClass::Highsec)), WormholeKind::Wandering);
+    assert_eq!(WormholeKind::classify(None, Some(&SystemClass::C1)), WormholeKind::Unknown);
+    assert_eq!(WormholeKind::classify(Some(&c1_statics), None), WormholeKind::Unknown);
+}
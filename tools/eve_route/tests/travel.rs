@@ -0,0 +1,100 @@
+mod common;
+
+use std::collections::HashMap;
+use chrono::{NaiveDate, NaiveDateTime, Duration};
+use eve_sde::{Gate, Position, System, SystemClass};
+use eve_route::*;
+use common::*;
+
+fn now() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2023, 12, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+}
+
+fn travel_time() -> TravelTime {
+    TravelTime {
+        gate_jump : Duration::minutes(1),
+        wormhole_jump : Duration::minutes(2),
+        warp : Duration::minutes(1),
+        system_warp : HashMap::new()
+    }
+}
+
+// 1 - 2 - 5 and 1 - 3 - 4 - 5 by gates, with 2 a long warp across, then 5 => 9
+// through an EOL wormhole. 1 - 6 - 7 - 8 - 10 - 9 by gates avoids it.
+fn eol_shortcut(expires_in : Duration) -> TestGraph {
+    let mut g = TestGraph::new(&(1..=10).map(|id| (id, SystemClass::Nullsec)).collect::<Vec<_>>());
+    g.gate(1, 2).gate(2, 5).gate(1, 3).gate(3, 4).gate(4, 5);
+    g.gate(1, 6).gate(6, 7).gate(7, 8).gate(8, 10).gate(10, 9);
+    let expiry = Some(ExpiryWindow { earliest : now() + expires_in, latest : now() + expires_in });
+    g.wormhole(5, 9, WormholeAttributes { life : WormholeLife::EOL, expiry, ..wormhole("ABC-123", "DEF-456") });
+    g
+}
+
+fn request(exclude_expiring : bool) -> RouteRequest {
+    let travel_time = TravelTime { system_warp : HashMap::from([(2, Duration::minutes(30))]), ..travel_time() };
+    RouteRequest { departure : Some(now()), travel_time, exclude_expiring, ..RouteRequest::new(1, 9) }
+}
+
+#[test]
+fn hop_etas() {
+    let g = eol_shortcut(Duration::hours(4));
+    let result = find_route(&g.graph, &request(false)).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 5, 9]);
+    let etas = result.hops.iter().map(|h| h.eta.unwrap() - now()).collect::<Vec<_>>();
+    assert_eq!(etas, vec![Duration::minutes(2), Duration::minutes(33), Duration::minutes(36)]);
+
+    // No departure, no ETAs
+    let request = RouteRequest { departure : None, ..request(false) };
+    assert!(find_route(&g.graph, &request).unwrap().hops.iter().all(|h| h.eta.is_none()));
+}
+
+#[test]
+fn slow_path_to_eol_is_rejected() {
+    // The shortest path reaches the wormhole after 32 minutes, the faster one after 7
+    let g = eol_shortcut(Duration::minutes(20));
+    let result = find_route(&g.graph, &request(true)).unwrap();
+    assert_eq!(route_ids(&result), vec![3, 4, 5, 9]);
+
+    // Without the check the wormhole is assumed to still be there
+    let result = find_route(&g.graph, &request(false)).unwrap();
+    assert_eq!(route_ids(&result), vec![2, 5, 9]);
+}
+
+#[test]
+fn unreachable_eol_is_avoided() {
+    let g = eol_shortcut(Duration::minutes(5));
+    let routes = find_routes(&g.graph, &request(true), 3).unwrap();
+    assert_eq!(route_ids(&routes[0]), vec![6, 7, 8, 10, 9]);
+    assert!(routes.iter().all(|r| r.wormholes().next().is_none()));
+}
+
+#[test]
+fn expired_journey_leg() {
+    // Going via 2 first makes the wormhole too late
+    let g = eol_shortcut(Duration::minutes(20));
+    let request = RouteRequest { via_systems : vec![2], ..request(true) };
+    let result = find_route(&g.graph, &request).unwrap();
+    assert!(result.wormholes().next().is_none());
+
+    let mut g = TestGraph::new(&[(1, SystemClass::C2), (2, SystemClass::C2), (3, SystemClass::C2)]);
+    let expiry = Some(ExpiryWindow { earliest : now(), latest : now() + Duration::minutes(6) });
+    g.wormhole(1, 2, wormhole("ABC-123", "DEF-456")).wormhole(2, 3, WormholeAttributes { expiry, ..wormhole("GHI-789", "JKL-012") });
+    let request = RouteRequest { via_systems : vec![2], departure : Some(now()), travel_time : travel_time(), exclude_expiring : true, ..RouteRequest::new(1, 3) };
+    assert_eq!(find_route(&g.graph, &request), Err(RouteError::NoPath));
+}
+
+#[test]
+fn warps_from_gate_distances() {
+    let gate = |destination, au : f64| Gate { destination, position : Position { x : au * METRES_PER_AU, y : 0.0, z : 0.0 } };
+    let near = System { gates : vec![gate(2, 0.0), gate(3, 1.0)], ..system(1, SystemClass::Highsec) };
+    let far = System { gates : vec![gate(1, 0.0), gate(3, 30.0), gate(4, 40.0)], ..system(2, SystemClass::Highsec) };
+    let dead_end = System { gates : vec![gate(1, 0.0)], ..system(3, SystemClass::Highsec) };
+
+    let warps = system_warps(&[near, far, dead_end], DEFAULT_WARP_SPEED);
+    assert!(warps[&1] < warps[&2]);
+    assert!(warps[&1] > Duration::seconds(10) && warps[&2] < Duration::minutes(2));
+    assert!(!warps.contains_key(&3));
+
+    // Full speed is only reached on longer warps, then it's cruising
+    assert_eq!(warp_duration(40.0 * METRES_PER_AU, 3.0) - warp_duration(10.0 * METRES_PER_AU, 3.0), Duration::seconds(10));
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+use sha2::{Digest, Sha256};
+
+use crate::{Sde, SDE_VERSION};
+
+// sde.bin starts with this header, then the Sde in postcard
+pub const SDE_MAGIC : [u8; 4] = *b"JPSD";
+
+// Bump when the header or the encoding changes
+pub const SDE_FORMAT_VERSION : u32 = 1;
+
+const HEADER_LENGTH : usize = 48;
+
+// Fixed layout, so a mismatch can be told apart from a corrupt file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdeHeader {
+    pub format_version : u32,
+    pub sde_version : u32, // Layout of Sde and System, as SDE_VERSION
+    pub sde_build : u32, // CCP's build number of the SDE it was made from, zero when unknown
+    pub content_hash : [u8; 32] // SHA-256 of everything after the header
+}
+
+impl SdeHeader {
+    fn to_bytes(&self) -> Vec<u8> {
+        [
+            SDE_MAGIC.as_slice(),
+            &self.format_version.to_le_bytes(),
+            &self.sde_version.to_le_bytes(),
+            &self.sde_build.to_le_bytes(),
+            &self.content_hash
+        ].concat()
+    }
+
+    // Only the header, so it's cheap and the content isn't checked
+    pub fn from_bytes(bytes : &[u8]) -> Result<Self, SdeBinaryError> {
+        if !is_binary_sde(bytes) { return Err(SdeBinaryError::NotBinary) }
+        if bytes.len() < HEADER_LENGTH { return Err(SdeBinaryError::Truncated) }
+        let word = |offset : usize| u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]);
+        let mut content_hash = [0u8; 32];
+        content_hash.copy_from_slice(&bytes[16..HEADER_LENGTH]);
+        Ok(SdeHeader { format_version : word(4), sde_version : word(8), sde_build : word(12), content_hash })
+    }
+
+    // As written to sde.hash, so a stored copy can be checked without downloading sde.bin
+    pub fn content_hash_hex(&self) -> String {
+        self.content_hash.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdeBinaryError {
+    NotBinary, // No magic, such as JSON or an error page
+    Truncated,
+    FormatVersion(u32), // Of the file, from another release of Journey Planner
+    SdeVersion(u32), // Layout of the systems in the file
+    ContentHash, // Damaged or changed after it was built
+    Incomplete(u32), // System with no position or region, as read from an older sde.json
+    Encoding(String)
+}
+
+impl fmt::Display for SdeBinaryError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SdeBinaryError::NotBinary => write!(f, "not a binary SDE"),
+            SdeBinaryError::Truncated => write!(f, "file is cut short"),
+            SdeBinaryError::FormatVersion(v) => write!(f, "format version {}, expected version {}, so it's from another release", v, SDE_FORMAT_VERSION),
+            SdeBinaryError::SdeVersion(v) => write!(f, "SDE version {}, expected version {}, so it's from another release", v, SDE_VERSION),
+            SdeBinaryError::ContentHash => write!(f, "content doesn't match its hash, so it's damaged or was changed after it was built"),
+            SdeBinaryError::Incomplete(id) => write!(f, "system {} has no position or region, so it was made from an older sde.json", id),
+            SdeBinaryError::Encoding(e) => write!(f, "{}", e)
+        }
+    }
+}
+
+pub fn is_binary_sde(bytes : &[u8]) -> bool {
+    bytes.starts_with(&SDE_MAGIC)
+}
+
+// An older layout can't be stamped with SDE_VERSION, as it's missing what that version adds
+fn check_complete(sde : &Sde) -> Result<(), SdeBinaryError> {
+    if sde.version != SDE_VERSION { return Err(SdeBinaryError::SdeVersion(sde.version)) }
+    match sde.systems.iter().find(|s| s.position.is_none() || s.region_name.is_empty()) {
+        Some(system) => Err(SdeBinaryError::Incomplete(system.id)),
+        None => Ok(())
+    }
+}
+
+pub fn encode_sde(sde : &Sde, sde_build : u32) -> Result<Vec<u8>, SdeBinaryError> {
+    check_complete(sde)?;
+    let content = postcard::to_allocvec(sde).map_err(|e| SdeBinaryError::Encoding(e.to_string()))?;
+    let header = SdeHeader {
+        format_version : SDE_FORMAT_VERSION,
+        sde_version : SDE_VERSION,
+        sde_build,
+        content_hash : Sha256::digest(&content).into()
+    };
+    Ok([header.to_bytes(), content].concat())
+}
+
+// Checks the header before anything is decoded, so a stale or damaged file gives a clear error
+pub fn decode_sde(bytes : &[u8]) -> Result<(SdeHeader, Sde), SdeBinaryError> {
+    let header = SdeHeader::from_bytes(bytes)?;
+    if header.format_version != SDE_FORMAT_VERSION { return Err(SdeBinaryError::FormatVersion(header.format_version)) }
+    if header.sde_version != SDE_VERSION { return Err(SdeBinaryError::SdeVersion(header.sde_version)) }
+
+    let content = &bytes[HEADER_LENGTH..];
+    if <[u8; 32]>::from(Sha256::digest(content)) != header.content_hash { return Err(SdeBinaryError::ContentHash) }
+
+    let sde = postcard::from_bytes::<Sde>(content).map_err(|e| SdeBinaryError::Encoding(e.to_string()))?;
+    check_complete(&sde)?;
+    Ok((header, sde))
+}
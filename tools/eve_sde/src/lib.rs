@@ -1,8 +1,15 @@
+use serde::{Serialize, Deserialize};
 use serde_tuple::*;
 use serde_repr::*;
 use num_enum::TryFromPrimitive;
 use std::cmp::Ordering;
 
+mod binary;
+mod wormhole;
+
+pub use binary::*;
+pub use wormhole::*;
+
 #[derive(Debug, Clone, Serialize_repr, Deserialize_repr, Eq, PartialEq, TryFromPrimitive)]
 #[repr(u8)]
 pub enum SystemClass {
@@ -26,13 +33,92 @@ pub enum SystemClass {
     Zarzakh = 50
 }
 
+// Bump when the layout of Sde or System changes
+pub const SDE_VERSION : u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sde {
+    pub version : u32,
+    pub systems : Vec<System>
+}
+
+// Version 1 files are a bare list of systems, without regions or positions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SdeFile {
+    Versioned(Sde),
+    Unversioned(Vec<SystemV1>)
+}
+
+impl From<SdeFile> for Sde {
+    fn from(file : SdeFile) -> Self {
+        match file {
+            SdeFile::Versioned(sde) => sde,
+            SdeFile::Unversioned(systems) => Sde { version : 1, systems : systems.into_iter().map(System::from).collect() }
+        }
+    }
+}
+
+// Metres
+#[derive(Debug, Clone, Copy, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct Position {
+    pub x : f64,
+    pub y : f64,
+    pub z : f64
+}
+
+impl Position {
+    pub fn distance(&self, other : &Position) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize_tuple, Deserialize_tuple)]
+pub struct Gate {
+    pub destination : u32, // System the gate leads to
+    pub position : Position // Within the system
+}
+
 #[derive(Debug, Clone, Serialize_tuple, Deserialize_tuple)]
 pub struct System {
     pub id : u32,
     pub name : String,
     pub security : i8,
     pub class : SystemClass,
-    pub neighbours : Vec<u32> // Neighbours are not repeated on both sides
+    pub neighbours : Vec<u32>, // Neighbours are not repeated on both sides
+    pub region_id : u32, // Zero when unknown
+    pub region_name : String,
+    pub constellation_id : u32, // Zero when unknown
+    pub constellation_name : String,
+    pub position : Option<Position>, // Within the universe
+    pub gates : Vec<Gate> // Every gate, unlike neighbours
+}
+
+#[derive(Debug, Clone, Deserialize_tuple)]
+pub struct SystemV1 {
+    pub id : u32,
+    pub name : String,
+    pub security : i8,
+    pub class : SystemClass,
+    pub neighbours : Vec<u32>
+}
+
+impl From<SystemV1> for System {
+    fn from(v1 : SystemV1) -> Self {
+        System {
+            id : v1.id,
+            name : v1.name,
+            security : v1.security,
+            class : v1.class,
+            neighbours : v1.neighbours,
+            region_id : 0,
+            region_name : String::new(),
+            constellation_id : 0,
+            constellation_name : String::new(),
+            position : None,
+            gates : vec![]
+        }
+    }
 }
 
 impl PartialEq for System {
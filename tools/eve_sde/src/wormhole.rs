@@ -0,0 +1,57 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+
+use crate::SystemClass;
+
+// Bump when the layout of WormholeTypes changes
+pub const WORMHOLE_TYPES_VERSION : u32 = 2;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WormholeTypes {
+    pub version : u32,
+    pub types : Vec<WormholeType>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WormholeType {
+    pub name : String, // Type code such as "N110"
+    pub attributes : BTreeMap<String, f64>, // Dogma attribute name to value
+
+    #[serde(default)]
+    pub static_in : Vec<SystemClass> // Classes where this type spawns as a static, empty for wandering types
+}
+
+impl WormholeType {
+    fn attribute(&self, name : &str) -> Option<f64> {
+        self.attributes.get(name).copied()
+    }
+
+    pub fn target_class(&self) -> Option<SystemClass> {
+        self.attribute("wormholeTargetSystemClass").and_then(|v| SystemClass::try_from(v as u8).ok())
+    }
+
+    // Minutes
+    pub fn max_stable_time(&self) -> Option<u32> {
+        self.attribute("wormholeMaxStableTime").map(|v| v as u32)
+    }
+
+    // Kilograms
+    pub fn max_stable_mass(&self) -> Option<u64> {
+        self.attribute("wormholeMaxStableMass").map(|v| v as u64)
+    }
+
+    // Kilograms
+    pub fn max_jump_mass(&self) -> Option<u64> {
+        self.attribute("wormholeMaxJumpMass").map(|v| v as u64)
+    }
+
+    // A known type appearing anywhere else is a wandering hole
+    pub fn is_static_in(&self, class : &SystemClass) -> bool {
+        self.static_in.contains(class)
+    }
+
+    // Kilograms per day
+    pub fn mass_regeneration(&self) -> Option<u64> {
+        self.attribute("wormholeMassRegeneration").map(|v| v as u64)
+    }
+}
@@ -0,0 +1,33 @@
+use std::fs;
+use eve_sde::*;
+
+// These check the static data in ./ref that ships with the planner. It's generated
+// from the SDE by eve_sde_cli, so run that first.
+
+fn ref_path(file : &str) -> String {
+    format!("{}/../../ref/{}", env!("CARGO_MANIFEST_DIR"), file)
+}
+
+fn read_ref(file : &str) -> Vec<u8> {
+    fs::read(ref_path(file)).unwrap_or_else(|e| panic!("Cannot read ./ref/{}, generate it with eve_sde_cli: {}", file, e))
+}
+
+#[test]
+fn shipped_wormhole_lifetimes() {
+    let types = serde_json::from_slice::<WormholeTypes>(&read_ref("wormholes.json")).unwrap();
+    let lifetime = |name : &str| types.types.iter().find(|t| t.name == name).and_then(|t| t.max_stable_time());
+
+    assert_eq!(lifetime("C247"), Some(16 * 60));
+    assert!(types.types.iter().all(|t| t.max_stable_time().is_some()));
+}
+
+#[test]
+fn shipped_sde_has_positions() {
+    let (header, sde) = decode_sde(&read_ref("sde.bin")).unwrap();
+    let jita = sde.systems.iter().find(|s| s.id == 30000142).unwrap();
+
+    assert_eq!(header.sde_version, SDE_VERSION);
+    assert_ne!(header.sde_build, 0);
+    assert!(jita.position.is_some());
+    assert_eq!(jita.region_name, "The Forge");
+}
@@ -0,0 +1,93 @@
+use eve_sde::*;
+
+#[test]
+fn unversioned_sde() {
+    let json = r#"[[30004238,"Gens",2,8,[]],[30004239,"Kamih",2,8,[30004238]]]"#;
+    let sde = Sde::from(serde_json::from_str::<SdeFile>(json).unwrap());
+    assert_eq!(sde.version, 1);
+    assert_eq!(sde.systems.len(), 2);
+    assert_eq!(sde.systems[1].neighbours, vec![30004238]);
+    assert_eq!(sde.systems[1].region_id, 0);
+    assert_eq!(sde.systems[1].position, None);
+}
+
+#[test]
+fn versioned_sde_round_trip() {
+    let system = System {
+        id : 30000142,
+        name : "Jita".to_owned(),
+        security : 9,
+        class : SystemClass::Highsec,
+        neighbours : vec![30000144],
+        region_id : 10000002,
+        region_name : "The Forge".to_owned(),
+        constellation_id : 20000020,
+        constellation_name : "Kimotoro".to_owned(),
+        position : Some(Position { x : -1.29e17, y : 6.07e16, z : 1.17e17 }),
+        gates : vec![Gate { destination : 30000144, position : Position { x : 1.0e12, y : 0.0, z : -2.0e11 } }]
+    };
+    let json = serde_json::to_string(&Sde { version : SDE_VERSION, systems : vec![system] }).unwrap();
+    let sde = Sde::from(serde_json::from_str::<SdeFile>(&json).unwrap());
+    assert_eq!(sde.version, SDE_VERSION);
+    assert_eq!(sde.systems[0].region_name, "The Forge");
+    assert_eq!(sde.systems[0].gates[0].destination, 30000144);
+    assert_eq!(sde.systems[0].position.unwrap().distance(&Position { x : -1.29e17, y : 6.07e16, z : 1.17e17 }), 0.0);
+}
+
+fn binary_sde() -> Sde {
+    let json = r#"[[30004238,"Gens",2,8,[]],[30004239,"Kamih",2,8,[30004238]]]"#;
+    let mut sde = Sde::from(serde_json::from_str::<SdeFile>(json).unwrap());
+    sde.version = SDE_VERSION;
+    for (system, x) in sde.systems.iter_mut().zip([0.0, 1.0e16]) {
+        system.region_name = "Domain".to_owned();
+        system.position = Some(Position { x, y : 0.0, z : 0.0 });
+    }
+    sde
+}
+
+#[test]
+fn binary_sde_round_trip() {
+    let bytes = encode_sde(&binary_sde(), 2_548_611).unwrap();
+    assert!(is_binary_sde(&bytes));
+    let (header, sde) = decode_sde(&bytes).unwrap();
+    assert_eq!(header.sde_build, 2_548_611);
+    assert_eq!(header.sde_version, SDE_VERSION);
+    assert_eq!(sde.systems.len(), 2);
+    assert_eq!(sde.systems[1].name, "Kamih");
+    assert_eq!(sde.systems[1].neighbours, vec![30004238]);
+
+    let hash = SdeHeader::from_bytes(&bytes).unwrap().content_hash_hex();
+    assert_eq!(hash.len(), 64);
+    assert_eq!(hash, header.content_hash_hex());
+    assert_ne!(hash, SdeHeader::from_bytes(&encode_sde(&Sde { version : SDE_VERSION, systems : vec![] }, 0).unwrap()).unwrap().content_hash_hex());
+}
+
+#[test]
+fn binary_sde_errors() {
+    let bytes = encode_sde(&binary_sde(), 0).unwrap();
+    assert_eq!(decode_sde(b"[[30004238]]").unwrap_err(), SdeBinaryError::NotBinary);
+    assert_eq!(decode_sde(&bytes[..20]).unwrap_err(), SdeBinaryError::Truncated);
+
+    let mut newer = bytes.clone();
+    newer[4] += 1;
+    assert_eq!(decode_sde(&newer).unwrap_err(), SdeBinaryError::FormatVersion(SDE_FORMAT_VERSION + 1));
+
+    let mut older = bytes.clone();
+    older[8] -= 1;
+    assert_eq!(decode_sde(&older).unwrap_err(), SdeBinaryError::SdeVersion(SDE_VERSION - 1));
+
+    let mut damaged = bytes.clone();
+    *damaged.last_mut().unwrap() ^= 1;
+    assert_eq!(decode_sde(&damaged).unwrap_err(), SdeBinaryError::ContentHash);
+}
+
+#[test]
+fn incomplete_sde_is_not_encoded() {
+    let json = r#"[[30004238,"Gens",2,8,[]],[30004239,"Kamih",2,8,[30004238]]]"#;
+    let older = Sde::from(serde_json::from_str::<SdeFile>(json).unwrap());
+    assert_eq!(encode_sde(&older, 0).unwrap_err(), SdeBinaryError::SdeVersion(1));
+
+    let mut unplaced = binary_sde();
+    unplaced.systems[1].position = None;
+    assert_eq!(encode_sde(&unplaced, 0).unwrap_err(), SdeBinaryError::Incomplete(30004239));
+}
@@ -1,6 +1,7 @@
-use std::fs::File;
+use std::env;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeMap};
 use std::io::{BufWriter, Write};
 use std::io::prelude::*;
 use serde::Deserialize;
@@ -17,7 +18,8 @@ struct NameSDE {
 
 #[derive(Debug, Clone, Deserialize)]
 struct StargateSDE {
-    destination: u32
+    destination: u32,
+    position: [f64; 3]
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -26,11 +28,11 @@ struct SystemSDE {
     id: u32,
     security: f64,
     stargates: HashMap<u32,StargateSDE>,
+    center: [f64; 3],
     #[serde(alias = "wormholeClassID")]
     class: Option<u8>,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 struct RegionSDE {
     #[serde(alias = "regionID")]
@@ -39,7 +41,6 @@ struct RegionSDE {
     class: Option<u8>,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize)]
 struct ConstellationSDE {
     #[serde(alias = "constellationID")]
@@ -48,6 +49,42 @@ struct ConstellationSDE {
     class: Option<u8>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct TypeNameSDE {
+    en: Option<String>
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeSDE {
+    #[serde(alias = "groupID")]
+    group_id: u32,
+    name: TypeNameSDE
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DogmaAttributeSDE {
+    name: String
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DogmaValueSDE {
+    #[serde(alias = "attributeID")]
+    attribute_id: u32,
+    value: f64
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TypeDogmaSDE {
+    #[serde(alias = "dogmaAttributes")]
+    attributes: Vec<DogmaValueSDE>
+}
+
+const WORMHOLE_GROUP_ID : u32 = 988;
+
+fn position([x, y, z] : [f64; 3]) -> Position {
+    Position { x, y, z }
+}
+
 enum IndexOrName {
     Index(usize),
     Name(String)
@@ -66,7 +103,99 @@ fn read_from_zip(archive : &mut zip::ZipArchive<File>, index_or_name: IndexOrNam
     (file.enclosed_name().unwrap().to_owned(), content)
 }
 
+// The SDE doesn't say which types are statics, so this is the static table by origin class.
+// Anything not listed here is treated as a wandering type.
+const STATICS : &[(&str, &[SystemClass])] = &[
+    ("H121", &[SystemClass::C1]), ("C125", &[SystemClass::C1]), ("O883", &[SystemClass::C1]),
+    ("M609", &[SystemClass::C1]), ("L614", &[SystemClass::C1]), ("S804", &[SystemClass::C1]),
+    ("N110", &[SystemClass::C1]), ("J244", &[SystemClass::C1]), ("Z060", &[SystemClass::C1]),
+    ("Z647", &[SystemClass::C2]), ("D382", &[SystemClass::C2]), ("O477", &[SystemClass::C2]),
+    ("Y683", &[SystemClass::C2]), ("N062", &[SystemClass::C2]), ("R474", &[SystemClass::C2]),
+    ("B274", &[SystemClass::C2]), ("A239", &[SystemClass::C2]), ("E545", &[SystemClass::C2]),
+    ("V301", &[SystemClass::C3]), ("I182", &[SystemClass::C3]), ("N968", &[SystemClass::C3]),
+    ("T405", &[SystemClass::C3]), ("N770", &[SystemClass::C3]), ("A982", &[SystemClass::C3]),
+    ("D845", &[SystemClass::C3]), ("U210", &[SystemClass::C3]), ("K346", &[SystemClass::C3]),
+    ("P060", &[SystemClass::C4]), ("N766", &[SystemClass::C4]), ("C247", &[SystemClass::C4]),
+    ("X877", &[SystemClass::C4]), ("H900", &[SystemClass::C4]), ("U574", &[SystemClass::C4]),
+    ("Y790", &[SystemClass::C5]), ("D364", &[SystemClass::C5]), ("M267", &[SystemClass::C5]),
+    ("E175", &[SystemClass::C5]), ("H296", &[SystemClass::C5]), ("V753", &[SystemClass::C5]),
+    ("D792", &[SystemClass::C5, SystemClass::C6]), ("C140", &[SystemClass::C5, SystemClass::C6]),
+    ("Z142", &[SystemClass::C5, SystemClass::C6]),
+    ("Q317", &[SystemClass::C6]), ("G024", &[SystemClass::C6]), ("L477", &[SystemClass::C6]),
+    ("Z457", &[SystemClass::C6]), ("V911", &[SystemClass::C6]), ("W237", &[SystemClass::C6]),
+    ("B520", &[SystemClass::C6]), ("C391", &[SystemClass::C6]), ("C248", &[SystemClass::C6])
+];
+
+fn wormhole_types(archive : &mut zip::ZipArchive<File>) -> WormholeTypes {
+    let (_, types_content) = read_from_zip(archive, IndexOrName::Name("sde/fsd/typeIDs.yaml".to_owned()));
+    let (_, dogma_content) = read_from_zip(archive, IndexOrName::Name("sde/fsd/typeDogma.yaml".to_owned()));
+    let (_, attributes_content) = read_from_zip(archive, IndexOrName::Name("sde/fsd/dogmaAttributes.yaml".to_owned()));
+
+    let types = serde_yaml::from_str::<HashMap<u32,TypeSDE>>(&types_content)
+        .expect("Failed to parse typeIDs.yaml");
+
+    let dogma = serde_yaml::from_str::<HashMap<u32,TypeDogmaSDE>>(&dogma_content)
+        .expect("Failed to parse typeDogma.yaml");
+
+    let attribute_names = serde_yaml::from_str::<HashMap<u32,DogmaAttributeSDE>>(&attributes_content)
+        .expect("Failed to parse dogmaAttributes.yaml")
+        .into_iter()
+        .map(|(id, a)| (id, a.name))
+        .collect::<HashMap<_,_>>();
+
+    let mut wormholes = types.iter().filter_map(|(type_id, t)| {
+        if t.group_id != WORMHOLE_GROUP_ID { return None }
+        let name = t.name.en.as_ref()?.strip_prefix("Wormhole ")?.to_owned();
+
+        let attributes = dogma.get(type_id).map(|d| {
+            d.attributes.iter().map(|a| {
+                let attribute_name = attribute_names.get(&a.attribute_id)
+                    .expect("Dogma attribute id doesn't match a name")
+                    .to_owned();
+                (attribute_name, a.value)
+            }).collect::<BTreeMap<_,_>>()
+        }).unwrap_or_default();
+
+        let static_in = STATICS.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, classes)| classes.to_vec())
+            .unwrap_or_default();
+
+        Some(WormholeType { name, attributes, static_in })
+    }).collect::<Vec<_>>();
+
+    wormholes.sort_by(|a, b| a.name.cmp(&b.name));
+    wormholes.dedup_by(|a, b| a.name == b.name);
+
+    for wormhole in &wormholes {
+        println!("{:?}", wormhole);
+    }
+
+    WormholeTypes { version : WORMHOLE_TYPES_VERSION, types : wormholes }
+}
+
+// sde.bin is what the planner loads, sde.json is kept for debugging. sde.hash
+// lets the planner check its stored copy without downloading sde.bin again.
+fn write_binary(sde : &Sde, sde_build : u32) {
+    let bytes = encode_sde(sde, sde_build).expect("Failed to encode sde.bin");
+    let header = SdeHeader::from_bytes(&bytes).expect("Failed to read sde.bin header");
+    fs::write("../../ref/sde.bin", &bytes).unwrap();
+    fs::write("../../ref/sde.hash", header.content_hash_hex()).unwrap();
+}
+
+// Arguments are the SDE build number, if known, and --from-json to only encode
+// ../../ref/sde.json as sde.bin, without needing sde.zip
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let sde_build = args.iter().find_map(|a| a.parse::<u32>().ok()).unwrap_or(0);
+
+    if args.iter().any(|a| a == "--from-json") {
+        let json = fs::read_to_string("../../ref/sde.json").expect("Failed to read sde.json");
+        let sde = Sde::from(serde_json::from_str::<SdeFile>(&json).expect("Failed to parse sde.json"));
+        write_binary(&sde, sde_build);
+        return
+    }
+
     let inputfile = File::open(Path::new("sde.zip")).unwrap();
 
     let mut archive = zip::ZipArchive::new(inputfile).unwrap();
@@ -120,6 +249,8 @@ fn main() {
 
 
     let mut gate_to_system = HashMap::<u32,u32>::new();
+    let mut all_gates = HashMap::<u32,u32>::new(); // Gate to the system it is in
+    let mut system_gates = HashMap::<u32,Vec<(u32,Position)>>::new(); // System to destination gates
     let mut systems = Vec::<System>::new();
 
     for (region_index, constellations) in regions {
@@ -128,12 +259,20 @@ fn main() {
         let region = serde_yaml::from_str::<RegionSDE>(&region_content)
             .expect("Failed to parse region data");
 
+        let region_name = names.get(&region.id)
+            .expect("Region id doesn't match a name")
+            .to_owned();
+
         for (constellation_index, system_indicies) in constellations {
             let (_, constellation_content) = read_from_zip(&mut archive, IndexOrName::Index(constellation_index));
 
             let constellation = serde_yaml::from_str::<ConstellationSDE>(&constellation_content)
                 .expect("Failed to parse contellation_content data");
 
+            let constellation_name = names.get(&constellation.id)
+                .expect("Constellation id doesn't match a name")
+                .to_owned();
+
             for system_index in system_indicies {
                 let (_, system_content) = read_from_zip(&mut archive, IndexOrName::Index(system_index));
         
@@ -149,6 +288,9 @@ fn main() {
                 let mut neighbours = Vec::<u32>::new();
         
                 for (source, stargate) in system.stargates {
+                    all_gates.insert(source, system.id);
+                    system_gates.entry(system.id).or_default().push((stargate.destination, position(stargate.position)));
+
                     match gate_to_system.get(&stargate.destination) {
                         None => {
                             gate_to_system.insert(source, system.id);
@@ -168,7 +310,19 @@ fn main() {
 
                 let class = SystemClass::try_from(rawclass).expect("Unexpected class");
 
-                let systemresult = System { id : system.id, name, security, neighbours, class };
+                let systemresult = System {
+                    id : system.id,
+                    name,
+                    security,
+                    class,
+                    neighbours,
+                    region_id : region.id,
+                    region_name : region_name.clone(),
+                    constellation_id : constellation.id,
+                    constellation_name : constellation_name.clone(),
+                    position : Some(position(system.center)),
+                    gates : vec![] // Filled in once every gate has been seen
+                };
                 println!("{:?}", systemresult);
                 systems.push(systemresult);
             }
@@ -179,8 +333,26 @@ fn main() {
         println!("System {} gate {} doesn't have matching gate in another system", system, gate);
     }
 
+    for system in &mut systems {
+        system.gates = system_gates.remove(&system.id).unwrap_or_default().into_iter()
+            .filter_map(|(destination_gate, position)| Some(Gate { destination : *all_gates.get(&destination_gate)?, position }))
+            .collect();
+        system.gates.sort_by_key(|g| g.destination);
+    }
+
+    let sde = Sde { version : SDE_VERSION, systems };
+
     let outputfile = File::create("../../ref/sde.json").unwrap();
     let mut writer = BufWriter::new(outputfile);
-    serde_json::to_writer(&mut writer, &systems).unwrap();
+    serde_json::to_writer(&mut writer, &sde).unwrap();
+    writer.flush().unwrap();
+
+    write_binary(&sde, sde_build);
+
+    let wormholes = wormhole_types(&mut archive);
+
+    let outputfile = File::create("../../ref/wormholes.json").unwrap();
+    let mut writer = BufWriter::new(outputfile);
+    serde_json::to_writer(&mut writer, &wormholes).unwrap();
     writer.flush().unwrap();
 }